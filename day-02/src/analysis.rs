@@ -0,0 +1,193 @@
+use std::fmt::{self, Write};
+
+use crate::{round_score, Outcome, Shape};
+
+// How the second column of the strategy guide is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpretation {
+    // part 1: X, Y, Z are the shapes to play
+    Shapes,
+    // part 2: X, Y, Z are the outcomes to aim for
+    Outcomes,
+}
+
+impl Interpretation {
+    pub fn from_name(name: &str) -> Option<Interpretation> {
+        match name {
+            "part-1" => Some(Interpretation::Shapes),
+            "part-2" => Some(Interpretation::Outcomes),
+            _ => None,
+        }
+    }
+
+    fn our_shape(self, theirs: Shape, token: &str) -> Option<Shape> {
+        match self {
+            Interpretation::Shapes => Shape::from_response_token(token),
+            Interpretation::Outcomes => {
+                Outcome::from_token(token).map(|outcome| theirs.response_for(outcome))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundReport {
+    pub opponent: Shape,
+    pub ours: Shape,
+    pub outcome: Outcome,
+    pub points: u32,
+    pub optimal_points: u32,
+}
+
+impl RoundReport {
+    fn new(opponent: Shape, ours: Shape) -> RoundReport {
+        RoundReport {
+            opponent,
+            ours,
+            outcome: Outcome::of(ours, opponent),
+            points: round_score(ours, opponent),
+            optimal_points: optimal_points(opponent),
+        }
+    }
+
+    pub fn lost(&self) -> u32 {
+        self.optimal_points - self.points
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    pub interpretation: Interpretation,
+    pub rounds: Vec<RoundReport>,
+}
+
+// The best score available against `opponent`. Playing the winning shape
+// always dominates: a win is worth at least 3 more than any other outcome
+// while shape scores differ by at most 2.
+fn optimal_points(opponent: Shape) -> u32 {
+    Shape::ALL
+        .into_iter()
+        .map(|ours| round_score(ours, opponent))
+        .max()
+        .expect("There is always at least one shape")
+}
+
+impl Analysis {
+    pub fn from_guide(input: &str, interpretation: Interpretation) -> Option<Analysis> {
+        let rounds = input
+            .lines()
+            .map(|line| {
+                let mut tokens = line.split_whitespace();
+                let opponent = tokens.next().and_then(Shape::from_opponent_token)?;
+                let ours = interpretation.our_shape(opponent, tokens.next()?)?;
+                Some(RoundReport::new(opponent, ours))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Analysis {
+            interpretation,
+            rounds,
+        })
+    }
+
+    pub fn total(&self) -> u32 {
+        self.rounds.iter().map(|r| r.points).sum()
+    }
+
+    pub fn optimal_total(&self) -> u32 {
+        self.rounds.iter().map(|r| r.optimal_points).sum()
+    }
+
+    pub fn lost(&self) -> u32 {
+        self.optimal_total() - self.total()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("round,opponent,ours,outcome,points,optimal_points,lost\n");
+        for (n, r) in self.rounds.iter().enumerate() {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                n + 1,
+                r.opponent,
+                r.ours,
+                r.outcome,
+                r.points,
+                r.optimal_points,
+                r.lost()
+            )
+            .expect("Writing to a String never fails");
+        }
+        csv
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let optimal = self.optimal_total();
+        let percent = if optimal == 0 {
+            100.0
+        } else {
+            f64::from(self.total()) * 100.0 / f64::from(optimal)
+        };
+        writeln!(f, "rounds:        {}", self.rounds.len())?;
+        writeln!(f, "guide score:   {}", self.total())?;
+        writeln!(f, "optimal score: {optimal}")?;
+        writeln!(
+            f,
+            "score lost:    {} ({percent:.1}% of optimal achieved)",
+            self.lost()
+        )
+    }
+}
+
+#[cfg(test)]
+mod analysis_tests {
+    use super::*;
+
+    const SAMPLE_GUIDE: &str = "A Y\nB X\nC Z\n";
+
+    #[test]
+    fn sample_totals_match_part_1_and_part_2() {
+        let shapes = Analysis::from_guide(SAMPLE_GUIDE, Interpretation::Shapes).unwrap();
+        assert_eq!(shapes.total(), crate::part_1(SAMPLE_GUIDE));
+        let outcomes = Analysis::from_guide(SAMPLE_GUIDE, Interpretation::Outcomes).unwrap();
+        assert_eq!(outcomes.total(), crate::part_2(SAMPLE_GUIDE));
+    }
+
+    #[test]
+    fn optimal_play_always_wins() {
+        assert_eq!(optimal_points(Shape::Rock), 2 + 6);
+        assert_eq!(optimal_points(Shape::Paper), 3 + 6);
+        assert_eq!(optimal_points(Shape::Scissors), 1 + 6);
+    }
+
+    #[test]
+    fn score_lost_is_measured_per_round() {
+        let analysis = Analysis::from_guide(SAMPLE_GUIDE, Interpretation::Shapes).unwrap();
+        let lost = analysis
+            .rounds
+            .iter()
+            .map(RoundReport::lost)
+            .collect::<Vec<_>>();
+        assert_eq!(lost, vec![0, 8, 1]);
+        assert_eq!(analysis.optimal_total(), 24);
+        assert_eq!(analysis.lost(), 9);
+    }
+
+    #[test]
+    fn csv_has_a_header_and_one_row_per_round() {
+        let analysis = Analysis::from_guide(SAMPLE_GUIDE, Interpretation::Outcomes).unwrap();
+        let csv = analysis.to_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "1,rock,rock,draw,4,8,4");
+        assert_eq!(lines[2], "2,paper,rock,lose,1,9,8");
+        assert_eq!(lines[3], "3,scissors,rock,win,7,7,0");
+    }
+
+    #[test]
+    fn a_malformed_line_is_rejected() {
+        assert!(Analysis::from_guide("A Y\nD X\n", Interpretation::Shapes).is_none());
+        assert!(Analysis::from_guide("A\n", Interpretation::Outcomes).is_none());
+    }
+}
@@ -1,4 +1,139 @@
-use std::{fs, io};
+use std::{env, fmt, fs, io};
+
+mod analysis;
+mod tournament;
+
+use analysis::{Analysis, Interpretation};
+use tournament::{
+    AlwaysRock, FrequencyCounter, GuideStrategy, RandomStrategy, Strategy, Tournament,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Shape {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl Shape {
+    const ALL: [Shape; 3] = [Shape::Rock, Shape::Paper, Shape::Scissors];
+
+    fn score(self) -> u32 {
+        match self {
+            Shape::Rock => 1,
+            Shape::Paper => 2,
+            Shape::Scissors => 3,
+        }
+    }
+
+    // The shape that this shape defeats
+    fn beats(self) -> Shape {
+        match self {
+            Shape::Rock => Shape::Scissors,
+            Shape::Paper => Shape::Rock,
+            Shape::Scissors => Shape::Paper,
+        }
+    }
+
+    // The shape that defeats this shape
+    fn beaten_by(self) -> Shape {
+        match self {
+            Shape::Rock => Shape::Paper,
+            Shape::Paper => Shape::Scissors,
+            Shape::Scissors => Shape::Rock,
+        }
+    }
+
+    // The shape to play against this shape to get `outcome`
+    fn response_for(self, outcome: Outcome) -> Shape {
+        match outcome {
+            Outcome::Lose => self.beats(),
+            Outcome::Draw => self,
+            Outcome::Win => self.beaten_by(),
+        }
+    }
+
+    fn from_opponent_token(token: &str) -> Option<Shape> {
+        match token {
+            "A" => Some(Shape::Rock),
+            "B" => Some(Shape::Paper),
+            "C" => Some(Shape::Scissors),
+            _ => None,
+        }
+    }
+
+    fn from_response_token(token: &str) -> Option<Shape> {
+        match token {
+            "X" => Some(Shape::Rock),
+            "Y" => Some(Shape::Paper),
+            "Z" => Some(Shape::Scissors),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Shape::Rock => "rock",
+            Shape::Paper => "paper",
+            Shape::Scissors => "scissors",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    fn score(self) -> u32 {
+        match self {
+            Outcome::Lose => 0,
+            Outcome::Draw => 3,
+            Outcome::Win => 6,
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Outcome> {
+        match token {
+            "X" => Some(Outcome::Lose),
+            "Y" => Some(Outcome::Draw),
+            "Z" => Some(Outcome::Win),
+            _ => None,
+        }
+    }
+
+    // The outcome of playing `mine` against `theirs`
+    fn of(mine: Shape, theirs: Shape) -> Outcome {
+        if mine == theirs {
+            Outcome::Draw
+        } else if mine.beats() == theirs {
+            Outcome::Win
+        } else {
+            Outcome::Lose
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Outcome::Lose => "lose",
+            Outcome::Draw => "draw",
+            Outcome::Win => "win",
+        };
+        f.write_str(name)
+    }
+}
+
+fn round_score(mine: Shape, theirs: Shape) -> u32 {
+    mine.score() + Outcome::of(mine, theirs).score()
+}
 
 fn second_token(line: &str) -> &str {
     line.split_whitespace()
@@ -76,8 +211,75 @@ fn part_2(input: &str) -> u32 {
     total
 }
 
+fn run_tournament(guide_path: &str, rounds: usize) -> io::Result<()> {
+    const RANDOM_SEED: u64 = 2022;
+    let players: Vec<Box<dyn Strategy>> = vec![
+        Box::new(GuideStrategy::from_file(guide_path)?),
+        Box::new(AlwaysRock),
+        Box::new(RandomStrategy::with_seed(RANDOM_SEED)),
+        Box::new(FrequencyCounter::default()),
+    ];
+    let mut tournament = Tournament::new(players);
+    let leaderboard = tournament.play_round_robin(rounds);
+    println!("day-02/tournament ({rounds} rounds per match)");
+    print!("{leaderboard}");
+    Ok(())
+}
+
+fn run_analysis(guide_path: &str, interpretation_name: Option<&str>) -> io::Result<()> {
+    let input = fs::read_to_string(guide_path)?;
+    let parse = |interpretation| {
+        Analysis::from_guide(&input, interpretation).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{guide_path} is not a valid strategy guide"),
+            )
+        })
+    };
+
+    match interpretation_name {
+        // A named interpretation gets the full per-round breakdown as CSV
+        Some(name) => {
+            let interpretation = Interpretation::from_name(name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown interpretation '{name}', expected part-1 or part-2"),
+                )
+            })?;
+            print!("{}", parse(interpretation)?.to_csv());
+        }
+        None => {
+            println!("day-02/analysis part-1");
+            print!("{}", parse(Interpretation::Shapes)?);
+            println!("day-02/analysis part-2");
+            print!("{}", parse(Interpretation::Outcomes)?);
+        }
+    }
+    Ok(())
+}
+
 fn main() -> io::Result<()> {
     const INPUT_FILE_PATH: &str = "input.txt";
+    const DEFAULT_TOURNAMENT_ROUNDS: usize = 1000;
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("tournament") => {
+            let rounds = match args.get(1) {
+                Some(count) => count.parse::<usize>().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Invalid round count '{count}', expected a whole number"),
+                    )
+                })?,
+                None => DEFAULT_TOURNAMENT_ROUNDS,
+            };
+            return run_tournament(INPUT_FILE_PATH, rounds);
+        }
+        Some("analyse") => return run_analysis(INPUT_FILE_PATH, args.get(1).map(String::as_str)),
+        _ => {}
+    }
+
     let input = fs::read_to_string(INPUT_FILE_PATH)?;
     let part_1_total = part_1(&input);
     println!("day-02/part-1 = {}", part_1_total);
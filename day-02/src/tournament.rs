@@ -0,0 +1,347 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    fmt, fs, io,
+};
+
+use crate::{round_score, Shape};
+
+pub trait Strategy {
+    fn name(&self) -> String;
+
+    // Choose a shape for the next round given every shape the opponent has
+    // played so far in the current match (oldest first)
+    fn next_shape(&mut self, opponent_history: &[Shape]) -> Shape;
+
+    // Called before every match so that stateful strategies start fresh
+    fn reset(&mut self) {}
+}
+
+// Plays the response column of a strategy guide (X, Y, Z read as shapes, as in
+// part 1), starting over once the guide is exhausted
+#[derive(Debug, Clone)]
+pub struct GuideStrategy {
+    shapes: Vec<Shape>,
+    cursor: usize,
+}
+
+impl GuideStrategy {
+    pub fn from_guide(input: &str) -> Option<GuideStrategy> {
+        let shapes = input
+            .lines()
+            .map(|line| {
+                line.split_whitespace()
+                    .nth(1)
+                    .and_then(Shape::from_response_token)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        (!shapes.is_empty()).then_some(GuideStrategy { shapes, cursor: 0 })
+    }
+
+    pub fn from_file(path: &str) -> io::Result<GuideStrategy> {
+        let input = fs::read_to_string(path)?;
+        GuideStrategy::from_guide(&input).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path} is not a valid strategy guide"),
+            )
+        })
+    }
+}
+
+impl Strategy for GuideStrategy {
+    fn name(&self) -> String {
+        "guide".to_owned()
+    }
+
+    fn next_shape(&mut self, _opponent_history: &[Shape]) -> Shape {
+        let shape = self.shapes[self.cursor];
+        self.cursor = (self.cursor + 1) % self.shapes.len();
+        shape
+    }
+
+    fn reset(&mut self) {
+        self.cursor = 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AlwaysRock;
+
+impl Strategy for AlwaysRock {
+    fn name(&self) -> String {
+        "always-rock".to_owned()
+    }
+
+    fn next_shape(&mut self, _opponent_history: &[Shape]) -> Shape {
+        Shape::Rock
+    }
+}
+
+// Uniformly random shapes from a xorshift generator. Every match replays the
+// same sequence so that tournaments are reproducible for a given seed.
+#[derive(Debug, Clone)]
+pub struct RandomStrategy {
+    seed: u64,
+    state: u64,
+}
+
+impl RandomStrategy {
+    pub fn with_seed(seed: u64) -> RandomStrategy {
+        // xorshift gets stuck on a zero state
+        let seed = if seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            seed
+        };
+        RandomStrategy { seed, state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn name(&self) -> String {
+        format!("random(seed={})", self.seed)
+    }
+
+    fn next_shape(&mut self, _opponent_history: &[Shape]) -> Shape {
+        Shape::ALL[(self.next_u64() % 3) as usize]
+    }
+
+    fn reset(&mut self) {
+        self.state = self.seed;
+    }
+}
+
+// Predicts that the opponent will repeat the shape they have played most often
+// and plays whatever beats it
+#[derive(Debug, Clone, Default)]
+pub struct FrequencyCounter {
+    counts: [u32; 3],
+    seen: usize,
+}
+
+impl FrequencyCounter {
+    fn index(shape: Shape) -> usize {
+        match shape {
+            Shape::Rock => 0,
+            Shape::Paper => 1,
+            Shape::Scissors => 2,
+        }
+    }
+
+    // Ties go to the earliest shape in `Shape::ALL`, so the first prediction
+    // of a match is always rock
+    fn predict(&self) -> Shape {
+        Shape::ALL
+            .into_iter()
+            .rev()
+            .max_by_key(|&shape| self.counts[Self::index(shape)])
+            .expect("There is always at least one shape")
+    }
+}
+
+impl Strategy for FrequencyCounter {
+    fn name(&self) -> String {
+        "frequency-counter".to_owned()
+    }
+
+    fn next_shape(&mut self, opponent_history: &[Shape]) -> Shape {
+        for &shape in &opponent_history[self.seen..] {
+            self.counts[Self::index(shape)] += 1;
+        }
+        self.seen = opponent_history.len();
+        self.predict().beaten_by()
+    }
+
+    fn reset(&mut self) {
+        *self = FrequencyCounter::default();
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Standing {
+    pub name: String,
+    pub total_score: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leaderboard {
+    pub standings: Vec<Standing>,
+}
+
+impl fmt::Display for Leaderboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>4}  {:<20} {:>10} {:>4} {:>4} {:>4}",
+            "rank", "strategy", "score", "W", "D", "L"
+        )?;
+        for (rank, s) in self.standings.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4}  {:<20} {:>10} {:>4} {:>4} {:>4}",
+                rank + 1,
+                s.name,
+                s.total_score,
+                s.wins,
+                s.draws,
+                s.losses
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Tournament {
+    players: Vec<Box<dyn Strategy>>,
+}
+
+impl Tournament {
+    pub fn new(players: Vec<Box<dyn Strategy>>) -> Tournament {
+        Tournament { players }
+    }
+
+    // Play `rounds` rounds between two strategies and return their scores
+    pub fn play_match(a: &mut dyn Strategy, b: &mut dyn Strategy, rounds: usize) -> (u32, u32) {
+        a.reset();
+        b.reset();
+        let mut a_history = Vec::with_capacity(rounds);
+        let mut b_history = Vec::with_capacity(rounds);
+        let (mut a_score, mut b_score) = (0, 0);
+        for _ in 0..rounds {
+            let a_shape = a.next_shape(&b_history);
+            let b_shape = b.next_shape(&a_history);
+            a_score += round_score(a_shape, b_shape);
+            b_score += round_score(b_shape, a_shape);
+            a_history.push(a_shape);
+            b_history.push(b_shape);
+        }
+        (a_score, b_score)
+    }
+
+    // Every strategy plays every other strategy once. Matches are won by the
+    // higher total score.
+    pub fn play_round_robin(&mut self, rounds: usize) -> Leaderboard {
+        let mut standings = self
+            .players
+            .iter()
+            .map(|p| Standing {
+                name: p.name(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        for i in 0..self.players.len() {
+            for j in i + 1..self.players.len() {
+                let (left, right) = self.players.split_at_mut(j);
+                let (a_score, b_score) =
+                    Tournament::play_match(left[i].as_mut(), right[0].as_mut(), rounds);
+                standings[i].total_score += a_score;
+                standings[j].total_score += b_score;
+                match a_score.cmp(&b_score) {
+                    Ordering::Greater => {
+                        standings[i].wins += 1;
+                        standings[j].losses += 1;
+                    }
+                    Ordering::Less => {
+                        standings[i].losses += 1;
+                        standings[j].wins += 1;
+                    }
+                    Ordering::Equal => {
+                        standings[i].draws += 1;
+                        standings[j].draws += 1;
+                    }
+                }
+            }
+        }
+
+        standings.sort_by_key(|s| (Reverse(s.total_score), Reverse(s.wins)));
+        Leaderboard { standings }
+    }
+}
+
+#[cfg(test)]
+mod tournament_tests {
+    use super::*;
+
+    const SAMPLE_GUIDE: &str = "A Y\nB X\nC Z\n";
+
+    #[test]
+    fn guide_strategy_replays_the_response_column_in_a_loop() {
+        let mut guide = GuideStrategy::from_guide(SAMPLE_GUIDE).unwrap();
+        let shapes = (0..4).map(|_| guide.next_shape(&[])).collect::<Vec<_>>();
+        assert_eq!(
+            shapes,
+            vec![Shape::Paper, Shape::Rock, Shape::Scissors, Shape::Paper]
+        );
+    }
+
+    #[test]
+    fn a_malformed_guide_is_rejected() {
+        assert!(GuideStrategy::from_guide("A Q\n").is_none());
+        assert!(GuideStrategy::from_guide("").is_none());
+    }
+
+    #[test]
+    fn the_sample_guide_scores_15_against_its_own_opponent_column() {
+        struct SampleOpponent(usize);
+        impl Strategy for SampleOpponent {
+            fn name(&self) -> String {
+                "sample-opponent".to_owned()
+            }
+            fn next_shape(&mut self, _: &[Shape]) -> Shape {
+                let shape = [Shape::Rock, Shape::Paper, Shape::Scissors][self.0];
+                self.0 += 1;
+                shape
+            }
+        }
+        let mut guide = GuideStrategy::from_guide(SAMPLE_GUIDE).unwrap();
+        let (score, _) = Tournament::play_match(&mut guide, &mut SampleOpponent(0), 3);
+        assert_eq!(score, 15);
+    }
+
+    #[test]
+    fn random_strategy_is_reproducible_after_reset() {
+        let mut random = RandomStrategy::with_seed(7);
+        let first = (0..20).map(|_| random.next_shape(&[])).collect::<Vec<_>>();
+        random.reset();
+        let second = (0..20).map(|_| random.next_shape(&[])).collect::<Vec<_>>();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn frequency_counter_always_beats_always_rock_after_the_first_round() {
+        let (counter_score, rock_score) =
+            Tournament::play_match(&mut FrequencyCounter::default(), &mut AlwaysRock, 10);
+        // Round 1: paper vs rock is a win for the counter, and so is every
+        // round after that
+        assert_eq!(counter_score, 10 * (2 + 6));
+        assert_eq!(rock_score, 10);
+    }
+
+    #[test]
+    fn round_robin_plays_every_pairing_once() {
+        let players: Vec<Box<dyn Strategy>> = vec![
+            Box::new(AlwaysRock),
+            Box::new(FrequencyCounter::default()),
+            Box::new(RandomStrategy::with_seed(1)),
+        ];
+        let leaderboard = Tournament::new(players).play_round_robin(50);
+        assert_eq!(leaderboard.standings.len(), 3);
+        for standing in &leaderboard.standings {
+            assert_eq!(standing.wins + standing.draws + standing.losses, 2);
+        }
+        assert_eq!(leaderboard.standings.last().unwrap().name, "always-rock");
+    }
+}
@@ -0,0 +1,136 @@
+use std::{collections::HashSet, time::Instant};
+
+use crate::{diagnostics::validate, part_1, part_2, score_byte};
+
+const ITEM_TYPES: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+// Generate `groups` groups of `group_size` rucksacks that are valid puzzle
+// input: every rucksack has exactly one misplaced item type and every group
+// shares exactly one badge.
+pub fn generate_input(groups: usize, group_size: usize, seed: u64) -> String {
+    let mut rng = XorShift(seed.max(1));
+    let mut output = String::new();
+    for _ in 0..groups {
+        let badge = ITEM_TYPES[rng.below(ITEM_TYPES.len())];
+        // Every other item type is kept out of at least one member so the
+        // badge is the only item type the whole group has in common
+        let excluded_from = ITEM_TYPES
+            .iter()
+            .map(|_| rng.below(group_size))
+            .collect::<Vec<_>>();
+
+        for member in 0..group_size {
+            let mut pool = ITEM_TYPES
+                .iter()
+                .zip(&excluded_from)
+                .filter(|&(&item, &excluded)| item != badge && excluded != member)
+                .map(|(&item, _)| item)
+                .collect::<Vec<_>>();
+            rng.shuffle(&mut pool);
+            let misplaced = pool[0];
+            let rest = &pool[1..];
+            let k = (4 + rng.below(12)).min((rest.len() - 1) / 2);
+
+            let mut front = vec![misplaced, badge];
+            front.extend_from_slice(&rest[..k]);
+            let mut back = vec![misplaced];
+            back.extend_from_slice(&rest[k..2 * k + 1]);
+
+            // Pad both compartments with repeats of their own item types
+            let compartment_len = front.len() + rng.below(8);
+            for compartment in [&mut front, &mut back] {
+                while compartment.len() < compartment_len {
+                    let repeat = compartment[rng.below(compartment.len())];
+                    compartment.push(repeat);
+                }
+                rng.shuffle(compartment);
+            }
+            output.extend(front.iter().chain(&back).map(|&b| b as char));
+            output.push('\n');
+        }
+    }
+    output
+}
+
+// The original HashSet based solution, kept as a baseline for the benchmark
+fn hash_set_totals(input: &str, group_size: usize) -> (u32, u32) {
+    let mut part_1_total = 0;
+    for line in input.lines() {
+        let bytes = line.as_bytes();
+        let (front, back) = bytes.split_at(bytes.len() / 2);
+        let front_set = HashSet::<u8>::from_iter(front.iter().copied());
+        let back_set = HashSet::<u8>::from_iter(back.iter().copied());
+        let &common = front_set
+            .intersection(&back_set)
+            .next()
+            .expect("At least one character in common");
+        part_1_total += score_byte(common);
+    }
+
+    let mut part_2_total = 0;
+    let lines = input.lines().collect::<Vec<_>>();
+    for group in lines.chunks(group_size) {
+        let mut byte_sets = group.iter().map(|s| HashSet::<u8>::from_iter(s.bytes()));
+        let mut first = byte_sets.next().expect("Groups are not empty");
+        for set in byte_sets {
+            first.retain(|b| set.contains(b));
+        }
+        let &common = first
+            .iter()
+            .next()
+            .expect("At least one character in common per group");
+        part_2_total += score_byte(common);
+    }
+    (part_1_total, part_2_total)
+}
+
+pub fn run(lines: usize, group_size: usize) {
+    const SEED: u64 = 2022;
+    let groups = (lines / group_size).max(1);
+    let input = generate_input(groups, group_size, SEED);
+    println!(
+        "day-03/bench: {} rucksacks, {} bytes",
+        groups * group_size,
+        input.len()
+    );
+
+    let start = Instant::now();
+    let baseline = hash_set_totals(&input, group_size);
+    let hash_set_time = start.elapsed();
+    println!("  hash set: {hash_set_time:?}");
+
+    let start = Instant::now();
+    let rucksacks = validate(&input, group_size).expect("Generated input is valid");
+    let bitmask = (part_1(&rucksacks), part_2(&rucksacks, group_size));
+    let bitmask_time = start.elapsed();
+    println!("  bitmask:  {bitmask_time:?}");
+
+    assert_eq!(baseline, bitmask, "Both solutions agree");
+    println!(
+        "  speedup:  {:.1}x",
+        hash_set_time.as_secs_f64() / bitmask_time.as_secs_f64()
+    );
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+use crate::priority::{IndexSet, PriorityTable};
+
+// A rucksack whose items are split evenly into any number of compartments,
+// with item symbols looked up in a `PriorityTable`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compartments {
+    compartments: Vec<IndexSet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompartmentError {
+    UnevenSplit { len: usize, compartments: usize },
+    // `column` is 1-based and counts chars, not bytes
+    UnknownItem { column: usize, item: char },
+}
+
+impl fmt::Display for CompartmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompartmentError::UnevenSplit { len, compartments } => write!(
+                f,
+                "{len} items can not be split evenly into {compartments} compartments"
+            ),
+            CompartmentError::UnknownItem { column, item } => {
+                write!(f, "item {item:?} at column {column} has no priority")
+            }
+        }
+    }
+}
+
+impl Compartments {
+    pub fn parse(
+        line: &str,
+        compartment_count: usize,
+        table: &PriorityTable,
+    ) -> Result<Compartments, CompartmentError> {
+        assert!(
+            compartment_count > 0,
+            "A rucksack has at least one compartment"
+        );
+        let indices = line
+            .chars()
+            .enumerate()
+            .map(|(i, item)| {
+                table.index_of(item).ok_or(CompartmentError::UnknownItem {
+                    column: i + 1,
+                    item,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if !indices.len().is_multiple_of(compartment_count) {
+            return Err(CompartmentError::UnevenSplit {
+                len: indices.len(),
+                compartments: compartment_count,
+            });
+        }
+
+        let compartment_len = indices.len() / compartment_count;
+        let compartments = if compartment_len == 0 {
+            vec![IndexSet::new(); compartment_count]
+        } else {
+            indices
+                .chunks(compartment_len)
+                .map(|chunk| chunk.iter().copied().collect())
+                .collect()
+        };
+        Ok(Compartments { compartments })
+    }
+
+    // Item types in every compartment
+    pub fn common_to_all(&self) -> IndexSet {
+        self.compartments
+            .iter()
+            .copied()
+            .reduce(IndexSet::intersection)
+            .unwrap_or_default()
+    }
+
+    // Item types in at least two compartments
+    pub fn common_to_any_two(&self) -> IndexSet {
+        let mut seen = IndexSet::new();
+        let mut repeated = IndexSet::new();
+        for &compartment in &self.compartments {
+            repeated = repeated.union(seen.intersection(compartment));
+            seen = seen.union(compartment);
+        }
+        repeated
+    }
+
+    // Item types shared by each pair of compartments (0-based), skipping pairs
+    // that share nothing
+    pub fn shared_pairs(&self) -> Vec<(usize, usize, IndexSet)> {
+        let mut pairs = Vec::new();
+        for (i, &a) in self.compartments.iter().enumerate() {
+            for (j, &b) in self.compartments.iter().enumerate().skip(i + 1) {
+                let shared = a.intersection(b);
+                if !shared.is_empty() {
+                    pairs.push((i, j, shared));
+                }
+            }
+        }
+        pairs
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fmt, str::FromStr};
+
+use crate::rucksack::{group_common, ItemSet, ParseRucksackError, Rucksack};
+
+// Everything that can be wrong with a rucksack list. Line numbers are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    InvalidRucksack {
+        line: usize,
+        error: ParseRucksackError,
+    },
+    NoMisplacedItem {
+        line: usize,
+    },
+    MultipleMisplacedItems {
+        line: usize,
+        items: ItemSet,
+    },
+    IncompleteGroup {
+        first_line: usize,
+        size: usize,
+        expected: usize,
+    },
+    NoBadge {
+        first_line: usize,
+    },
+    MultipleBadges {
+        first_line: usize,
+        items: ItemSet,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::InvalidRucksack { line, error } => write!(f, "line {line}: {error}"),
+            Diagnostic::NoMisplacedItem { line } => {
+                write!(f, "line {line}: no item type is in both compartments")
+            }
+            Diagnostic::MultipleMisplacedItems { line, items } => write!(
+                f,
+                "line {line}: {} item types are in both compartments: {items}",
+                items.len()
+            ),
+            Diagnostic::IncompleteGroup {
+                first_line,
+                size,
+                expected,
+            } => write!(
+                f,
+                "group starting at line {first_line}: has {size} rucksacks, expected {expected}"
+            ),
+            Diagnostic::NoBadge { first_line } => write!(
+                f,
+                "group starting at line {first_line}: no item type is shared by every rucksack"
+            ),
+            Diagnostic::MultipleBadges { first_line, items } => write!(
+                f,
+                "group starting at line {first_line}: {} item types are shared by every rucksack: {items}",
+                items.len()
+            ),
+        }
+    }
+}
+
+// Parse every rucksack, recording each problem found. When `check_misplaced`
+// is set, each rucksack must also have exactly one misplaced item type.
+// Rucksacks that could not be parsed are `None`.
+fn check_rucksacks(
+    input: &str,
+    check_misplaced: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Option<Rucksack>> {
+    input
+        .lines()
+        .zip(1..)
+        .map(|(text, line)| match Rucksack::from_str(text) {
+            Ok(rucksack) => {
+                let misplaced = rucksack.misplaced();
+                if check_misplaced {
+                    match misplaced.len() {
+                        0 => diagnostics.push(Diagnostic::NoMisplacedItem { line }),
+                        1 => {}
+                        _ => diagnostics.push(Diagnostic::MultipleMisplacedItems {
+                            line,
+                            items: misplaced,
+                        }),
+                    }
+                }
+                Some(rucksack)
+            }
+            Err(error) => {
+                diagnostics.push(Diagnostic::InvalidRucksack { line, error });
+                None
+            }
+        })
+        .collect()
+}
+
+fn finish(
+    parsed: Vec<Option<Rucksack>>,
+    diagnostics: Vec<Diagnostic>,
+) -> Result<Vec<Rucksack>, Vec<Diagnostic>> {
+    if diagnostics.is_empty() {
+        Ok(parsed.into_iter().flatten().collect())
+    } else {
+        Err(diagnostics)
+    }
+}
+
+// Parse every rucksack without checking compartments or groups, for uses
+// such as badge discovery that do not depend on the misplaced items
+pub fn parse_rucksacks(input: &str) -> Result<Vec<Rucksack>, Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    let parsed = check_rucksacks(input, false, &mut diagnostics);
+    finish(parsed, diagnostics)
+}
+
+// Parse and check every rucksack and every group of `group_size` consecutive
+// rucksacks. Either every rucksack has exactly one misplaced item type and
+// every group exactly one badge, or every problem found is returned.
+pub fn validate(input: &str, group_size: usize) -> Result<Vec<Rucksack>, Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    let parsed = check_rucksacks(input, true, &mut diagnostics);
+
+    for (group, first_line) in parsed.chunks(group_size).zip((1..).step_by(group_size)) {
+        if group.len() != group_size {
+            diagnostics.push(Diagnostic::IncompleteGroup {
+                first_line,
+                size: group.len(),
+                expected: group_size,
+            });
+            continue;
+        }
+        // Groups with unparseable members have already been reported
+        let Some(group) = group.iter().cloned().collect::<Option<Vec<_>>>() else {
+            continue;
+        };
+        let badges = group_common(&group);
+        match badges.len() {
+            0 => diagnostics.push(Diagnostic::NoBadge { first_line }),
+            1 => {}
+            _ => diagnostics.push(Diagnostic::MultipleBadges {
+                first_line,
+                items: badges,
+            }),
+        }
+    }
+
+    finish(parsed, diagnostics)
+}
@@ -0,0 +1,117 @@
+use crate::rucksack::{ItemSet, Rucksack};
+
+// A group of three rucksacks, by index into the rucksack list, and the one
+// item type they all carry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BadgeGroup {
+    pub members: [usize; 3],
+    pub badge: ItemSet,
+}
+
+// Partition shuffled rucksacks into groups of three that each share exactly
+// one item type. This is an exact cover problem: every candidate triple that
+// shares a single item type is enumerated up front, then a backtracking search
+// repeatedly covers the rucksack with the fewest remaining candidate triples.
+// Returns `None` only once every possibility has been ruled out.
+pub fn discover_groups(rucksacks: &[Rucksack]) -> Option<Vec<BadgeGroup>> {
+    if !rucksacks.len().is_multiple_of(3) {
+        return None;
+    }
+
+    let items = rucksacks
+        .iter()
+        .map(Rucksack::all_items)
+        .collect::<Vec<_>>();
+    let mut candidates = Vec::new();
+    let mut containing = vec![Vec::new(); rucksacks.len()];
+    for i in 0..items.len() {
+        for j in i + 1..items.len() {
+            let pair = items[i].intersection(items[j]);
+            if pair.is_empty() {
+                continue;
+            }
+            for (k, &third) in items.iter().enumerate().skip(j + 1) {
+                let badge = pair.intersection(third);
+                if badge.len() == 1 {
+                    for member in [i, j, k] {
+                        containing[member].push(candidates.len());
+                    }
+                    candidates.push(BadgeGroup {
+                        members: [i, j, k],
+                        badge,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut search = Search {
+        candidates: &candidates,
+        containing: &containing,
+        used: vec![false; rucksacks.len()],
+        chosen: Vec::with_capacity(rucksacks.len() / 3),
+    };
+    search
+        .solve()
+        .then(|| search.chosen.iter().map(|&c| candidates[c]).collect())
+}
+
+struct Search<'a> {
+    candidates: &'a [BadgeGroup],
+    containing: &'a [Vec<usize>],
+    used: Vec<bool>,
+    chosen: Vec<usize>,
+}
+
+impl Search<'_> {
+    fn is_available(&self, candidate: usize) -> bool {
+        self.candidates[candidate]
+            .members
+            .iter()
+            .all(|&m| !self.used[m])
+    }
+
+    // The unused rucksack with the fewest available triples, or `None` when
+    // every rucksack has been placed in a group
+    fn most_constrained(&self) -> Option<(usize, usize)> {
+        (0..self.used.len())
+            .filter(|&r| !self.used[r])
+            .map(|r| {
+                let options = self.containing[r]
+                    .iter()
+                    .filter(|&&c| self.is_available(c))
+                    .count();
+                (r, options)
+            })
+            .min_by_key(|&(_, options)| options)
+    }
+
+    fn set_used(&mut self, candidate: usize, used: bool) {
+        for &member in &self.candidates[candidate].members {
+            self.used[member] = used;
+        }
+    }
+
+    fn solve(&mut self) -> bool {
+        let Some((rucksack, options)) = self.most_constrained() else {
+            return true;
+        };
+        if options == 0 {
+            return false;
+        }
+        let containing = self.containing;
+        for &candidate in &containing[rucksack] {
+            if !self.is_available(candidate) {
+                continue;
+            }
+            self.set_used(candidate, true);
+            self.chosen.push(candidate);
+            if self.solve() {
+                return true;
+            }
+            self.chosen.pop();
+            self.set_used(candidate, false);
+        }
+        false
+    }
+}
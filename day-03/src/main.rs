@@ -1,4 +1,17 @@
-use std::{collections::HashSet, fs, io};
+use std::{env, fs, io, str::FromStr};
+
+mod bench;
+mod compartments;
+mod diagnostics;
+mod discovery;
+mod planner;
+mod priority;
+mod rucksack;
+
+use compartments::Compartments;
+use diagnostics::Diagnostic;
+use priority::PriorityTable;
+use rucksack::{group_common, Rucksack};
 
 fn score_byte(byte: u8) -> u32 {
     const LOWER_CASE_OFFSET: u8 = b'a';
@@ -11,41 +24,483 @@ fn score_byte(byte: u8) -> u32 {
     }
 }
 
+// Both parts expect rucksacks that have passed `diagnostics::validate`
+fn part_1(rucksacks: &[Rucksack]) -> u32 {
+    rucksacks
+        .iter()
+        .map(|rucksack| {
+            rucksack
+                .misplaced()
+                .single_priority()
+                .expect("Exactly one character in common")
+        })
+        .sum()
+}
+
+fn part_2(rucksacks: &[Rucksack], group_size: usize) -> u32 {
+    rucksacks
+        .chunks(group_size)
+        .map(|group| {
+            group_common(group)
+                .single_priority()
+                .expect("Exactly one character in common per group")
+        })
+        .sum()
+}
+
+fn report_diagnostics(path: &str, diagnostics: &[Diagnostic]) -> io::Error {
+    for diagnostic in diagnostics {
+        eprintln!("{path}: {diagnostic}");
+    }
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} problems found in {path}", diagnostics.len()),
+    )
+}
+
+// Find badge groups in a rucksack list whose lines have been shuffled
+fn run_discovery(path: &str) -> io::Result<()> {
+    let input = fs::read_to_string(path)?;
+    let rucksacks = diagnostics::parse_rucksacks(&input)
+        .map_err(|diagnostics| report_diagnostics(path, &diagnostics))?;
+
+    match discovery::discover_groups(&rucksacks) {
+        Some(groups) => {
+            for group in &groups {
+                let [a, b, c] = group.members.map(|m| m + 1);
+                println!("lines {a}, {b}, {c}: badge {}", group.badge);
+            }
+            let total: u32 = groups.iter().map(|g| g.badge.priority_sum()).sum();
+            println!("day-03/discover = {total} ({} groups)", groups.len());
+        }
+        None => println!(
+            "day-03/discover: the {} rucksacks can not be split into groups of three with one badge each",
+            rucksacks.len()
+        ),
+    }
+    Ok(())
+}
+
+// Plan how to rearrange every rucksack so no item type is in both compartments
+fn run_planner(path: &str) -> io::Result<()> {
+    let input = fs::read_to_string(path)?;
+    let mut total_swaps = 0;
+    let mut impossible = 0;
+    let mut invalid = 0;
+    for (text, line) in input.lines().zip(1..) {
+        if let Err(error) = Rucksack::from_str(text) {
+            eprintln!("{path}: line {line}: {error}");
+            invalid += 1;
+            continue;
+        }
+        match planner::plan(text) {
+            Some(plan) => {
+                println!("line {line}: {} swaps: {}", plan.swaps, plan.rearranged);
+                total_swaps += plan.swaps;
+            }
+            None => {
+                println!("line {line}: no arrangement keeps item types in one compartment");
+                impossible += 1;
+            }
+        }
+    }
+    println!(
+        "day-03/plan = {total_swaps} swaps ({impossible} rucksacks can not be fixed, {invalid} lines are not rucksacks)"
+    );
+    Ok(())
+}
+
+// Report items shared between compartments when rucksacks have
+// `compartment_count` compartments and, optionally, a custom priority table
+fn run_compartments(
+    path: &str,
+    compartment_count: usize,
+    table_path: Option<&str>,
+) -> io::Result<()> {
+    let table = match table_path {
+        Some(table_path) => {
+            let text = fs::read_to_string(table_path)?;
+            PriorityTable::from_str(&text).map_err(|error| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("{table_path}: {error}"))
+            })?
+        }
+        None => PriorityTable::standard(),
+    };
+
+    let input = fs::read_to_string(path)?;
+    let (mut all_total, mut any_two_total) = (0, 0);
+    for (text, line) in input.lines().zip(1..) {
+        let rucksack = match Compartments::parse(text, compartment_count, &table) {
+            Ok(rucksack) => rucksack,
+            Err(error) => {
+                eprintln!("{path}: line {line}: {error}");
+                continue;
+            }
+        };
+        let all = rucksack.common_to_all();
+        let any_two = rucksack.common_to_any_two();
+        all_total += table.priority_sum(all);
+        any_two_total += table.priority_sum(any_two);
+
+        let pairs = rucksack
+            .shared_pairs()
+            .iter()
+            .map(|&(a, b, items)| format!("{}&{}={}", a + 1, b + 1, table.symbols_of(items)))
+            .collect::<Vec<_>>();
+        println!(
+            "line {line}: all [{}] any two [{}] pairs [{}]",
+            table.symbols_of(all),
+            table.symbols_of(any_two),
+            pairs.join(" ")
+        );
+    }
+    println!("day-03/compartments all = {all_total}");
+    println!("day-03/compartments any-two = {any_two_total}");
+    Ok(())
+}
+
 fn main() -> io::Result<()> {
     const INPUT_FILE_PATH: &str = "input.txt";
+    const GROUP_SIZE: usize = 3;
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("bench") => {
+            const DEFAULT_BENCH_LINES: usize = 1_000_000;
+            let lines = args
+                .get(1)
+                .map(|s| s.parse::<usize>().expect("Line count is a valid usize"))
+                .unwrap_or(DEFAULT_BENCH_LINES);
+            bench::run(lines, GROUP_SIZE);
+            return Ok(());
+        }
+        Some("discover") => {
+            let path = args.get(1).map_or(INPUT_FILE_PATH, String::as_str);
+            return run_discovery(path);
+        }
+        Some("plan") => {
+            let path = args.get(1).map_or(INPUT_FILE_PATH, String::as_str);
+            return run_planner(path);
+        }
+        Some("compartments") => {
+            let compartment_count = args
+                .get(1)
+                .map(|s| {
+                    s.parse::<usize>()
+                        .expect("Compartment count is a valid usize")
+                })
+                .filter(|&k| k > 0)
+                .expect("Usage: compartments <count> [priority-table] [input]");
+            let table_path = args.get(2).map(String::as_str);
+            let path = args.get(3).map_or(INPUT_FILE_PATH, String::as_str);
+            return run_compartments(path, compartment_count, table_path);
+        }
+        _ => {}
+    }
 
     let input = fs::read_to_string(INPUT_FILE_PATH)?;
+    let rucksacks = diagnostics::validate(&input, GROUP_SIZE)
+        .map_err(|diagnostics| report_diagnostics(INPUT_FILE_PATH, &diagnostics))?;
 
-    let mut part_1_total = 0;
-    for line in input.lines() {
-        let bytes = line.as_bytes();
-        let (front, back) = bytes.split_at(bytes.len() / 2);
-        let front_set = HashSet::<u8>::from_iter(front.iter().copied());
-        let back_set = HashSet::<u8>::from_iter(back.iter().copied());
-        let &common = front_set
-            .intersection(&back_set)
-            .next()
-            .expect("At least one character in common");
-        part_1_total += score_byte(common);
-    }
+    let part_1_total = part_1(&rucksacks);
     println!("day-03/part-1 = {}", part_1_total);
 
-    const GROUP_SIZE: usize = 3;
-    let mut part_2_total = 0;
-    let lines = input.lines().collect::<Vec<_>>();
-    for group in lines.chunks(GROUP_SIZE) {
-        let mut byte_sets = group.iter().map(|s| HashSet::<u8>::from_iter(s.bytes()));
-        let expect_message = format!("There are {} byte sets", GROUP_SIZE);
-        let mut first = byte_sets.next().expect(&expect_message);
-        let second = byte_sets.next().expect(&expect_message);
-        let third = byte_sets.next().expect(&expect_message);
-        first.retain(|b| second.contains(b) && third.contains(b));
-        let &common = first
-            .iter()
-            .next()
-            .expect("At least one character in common per group");
-        part_2_total += score_byte(common);
-    }
+    let part_2_total = part_2(&rucksacks, GROUP_SIZE);
     println!("day-03/part-2 = {}", part_2_total);
     Ok(())
 }
+
+#[cfg(test)]
+mod day_03_tests {
+    use std::str::FromStr;
+
+    use crate::{
+        bench::generate_input,
+        compartments::{CompartmentError, Compartments},
+        diagnostics::{parse_rucksacks, validate, Diagnostic},
+        discovery::discover_groups,
+        part_1, part_2,
+        planner::plan,
+        priority::{ParsePriorityTableError, PriorityTable},
+        rucksack::{group_common, ItemSet, ParseRucksackError, Rucksack},
+        score_byte,
+    };
+
+    const SAMPLE: &str = include_str!("../sample_input.txt");
+
+    fn rucksack(line: &str) -> Rucksack {
+        Rucksack::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn sample_part_1() {
+        let rucksacks = validate(SAMPLE, 3).unwrap();
+        assert_eq!(part_1(&rucksacks), 157);
+    }
+
+    #[test]
+    fn sample_part_2() {
+        let rucksacks = validate(SAMPLE, 3).unwrap();
+        assert_eq!(part_2(&rucksacks, 3), 70);
+    }
+
+    #[test]
+    fn item_set_bits_are_item_priorities() {
+        let set = ItemSet::from_bytes(b"aZzA");
+        assert_eq!(set.priorities().collect::<Vec<_>>(), vec![1, 26, 27, 52]);
+    }
+
+    #[test]
+    fn misplaced_item_is_in_both_compartments() {
+        let rucksack = rucksack("vJrwpWtwJgWrhcsFMMfFFhFp");
+        assert_eq!(rucksack.misplaced(), ItemSet::from_bytes(b"p"));
+    }
+
+    #[test]
+    fn groups_can_have_any_size() {
+        let group = ["abcX", "Xdef", "ghXi", "jXkl"].map(rucksack);
+        assert_eq!(group_common(&group), ItemSet::from_bytes(b"X"));
+        assert_eq!(part_2(&group, 4), 50);
+        assert_eq!(part_2(&group[..2], 2), 50);
+    }
+
+    #[test]
+    fn generated_input_has_one_badge_per_group() {
+        let input = generate_input(50, 3, 7);
+        assert!(validate(&input, 3).is_ok());
+    }
+
+    #[test]
+    fn rucksacks_must_have_an_even_number_of_letters() {
+        assert_eq!(
+            Rucksack::from_str("abcab"),
+            Err(ParseRucksackError::OddLength(5))
+        );
+        assert_eq!(
+            Rucksack::from_str("ab1a"),
+            Err(ParseRucksackError::InvalidItem {
+                column: 3,
+                item: '1'
+            })
+        );
+        assert_eq!(
+            Rucksack::from_str("aéa"),
+            Err(ParseRucksackError::InvalidItem {
+                column: 2,
+                item: 'é'
+            })
+        );
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let input = "abca\nabab\nab-a\nxyzx\nzwwq\nqrst\nxzzq\n";
+        let diagnostics = validate(input, 3).unwrap_err();
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::MultipleMisplacedItems {
+                    line: 2,
+                    items: ItemSet::from_bytes(b"ab")
+                },
+                Diagnostic::InvalidRucksack {
+                    line: 3,
+                    error: ParseRucksackError::InvalidItem {
+                        column: 3,
+                        item: '-'
+                    }
+                },
+                Diagnostic::NoMisplacedItem { line: 6 },
+                Diagnostic::NoBadge { first_line: 4 },
+                Diagnostic::IncompleteGroup {
+                    first_line: 7,
+                    size: 1,
+                    expected: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ambiguous_badges_list_the_offending_items() {
+        let diagnostics = validate("abab\nbaab\nabba\n", 3).unwrap_err();
+        let group = diagnostics
+            .iter()
+            .find(|d| matches!(d, Diagnostic::MultipleBadges { .. }))
+            .unwrap();
+        assert_eq!(
+            group.to_string(),
+            "group starting at line 1: 2 item types are shared by every rucksack: ab"
+        );
+    }
+
+    #[test]
+    fn badge_groups_are_found_in_shuffled_input() {
+        let shuffled = [4, 0, 2, 5, 1, 3]
+            .map(|i| SAMPLE.lines().nth(i).unwrap())
+            .join("\n");
+        let rucksacks = parse_rucksacks(&shuffled).unwrap();
+        let groups = discover_groups(&rucksacks).unwrap();
+        assert_eq!(groups.len(), 2);
+        for group in &groups {
+            let members = group.members.map(|m| rucksacks[m].clone());
+            assert_eq!(group_common(&members), group.badge);
+            assert_eq!(group.badge.len(), 1);
+        }
+        let mut covered = groups.iter().flat_map(|g| g.members).collect::<Vec<_>>();
+        covered.sort();
+        assert_eq!(covered, (0..6).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn discovery_does_not_need_one_misplaced_item() {
+        // "abab" has two misplaced item types and "cdef" none, which only
+        // matters for part 1
+        let rucksacks = parse_rucksacks("abab\nacdc\naefe\ncdef\n").unwrap();
+        assert_eq!(rucksacks.len(), 4);
+        assert_eq!(
+            parse_rucksacks("abab\nab1b\n").unwrap_err(),
+            vec![Diagnostic::InvalidRucksack {
+                line: 2,
+                error: ParseRucksackError::InvalidItem {
+                    column: 3,
+                    item: '1'
+                }
+            }]
+        );
+    }
+
+    #[test]
+    fn no_partition_exists_when_a_rucksack_shares_nothing() {
+        let rucksacks = ["abca", "adeb", "bfgb", "XYZX", "acac", "bcbd"].map(rucksack);
+        assert!(discover_groups(&rucksacks).is_none());
+        assert!(discover_groups(&rucksacks[..4]).is_none());
+    }
+
+    #[test]
+    fn planner_swaps_the_misplaced_items() {
+        let fixed = plan("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap();
+        assert_eq!(fixed.swaps, 1);
+        assert!(rucksack(&fixed.rearranged).misplaced().is_empty());
+    }
+
+    #[test]
+    fn planner_chooses_the_cheaper_side_for_each_item_type() {
+        // Moving the single back `a` is cheaper than moving the three front
+        // ones, and `b` goes back to make room for it
+        let fixed = plan("aaabcdae").unwrap();
+        assert_eq!(fixed.swaps, 1);
+        assert_eq!(fixed.rearranged, "aaaacdbe");
+
+        assert_eq!(plan("abab").unwrap().swaps, 1);
+        assert_eq!(plan("abcd").unwrap().swaps, 0);
+    }
+
+    #[test]
+    fn planner_reports_rucksacks_that_can_not_be_fixed() {
+        assert!(plan("aaab").is_none());
+        assert!(plan("aaaaab").is_none());
+    }
+
+    #[test]
+    fn every_planned_rucksack_is_fixed() {
+        let input = generate_input(30, 3, 11);
+        for line in input.lines() {
+            let fixed = plan(line).unwrap();
+            assert!(rucksack(&fixed.rearranged).misplaced().is_empty());
+            assert!(fixed.swaps >= 1);
+        }
+    }
+
+    #[test]
+    fn the_standard_table_matches_score_byte() {
+        let table = PriorityTable::standard();
+        for byte in (b'a'..=b'z').chain(b'A'..=b'Z') {
+            let index = table.index_of(byte as char).unwrap();
+            assert_eq!(table.priority(index), score_byte(byte));
+        }
+        assert_eq!(table.index_of('é'), None);
+    }
+
+    #[test]
+    fn two_compartments_with_the_standard_table_match_part_1() {
+        let table = PriorityTable::standard();
+        let total: u32 = SAMPLE
+            .lines()
+            .map(|line| Compartments::parse(line, 2, &table).unwrap())
+            .map(|c| table.priority_sum(c.common_to_all()))
+            .sum();
+        assert_eq!(total, 157);
+    }
+
+    #[test]
+    fn the_first_table_symbol_has_index_zero() {
+        // 'a' is index 0 in the standard table but priority 1
+        let table = PriorityTable::standard();
+        let rucksack = Compartments::parse("abac", 2, &table).unwrap();
+        let common = rucksack.common_to_all();
+        assert_eq!(common.indices().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(table.symbols_of(common), "a");
+        assert_eq!(table.priority_sum(common), 1);
+    }
+
+    #[test]
+    fn custom_tables_support_non_ascii_symbols() {
+        let table = PriorityTable::from_str("é 10\nß 20\n★ 5\n\nx 1\n").unwrap();
+        let rucksack = Compartments::parse("éxßßé★x★é", 3, &table).unwrap();
+        assert_eq!(table.symbols_of(rucksack.common_to_all()), "é");
+        assert_eq!(table.symbols_of(rucksack.common_to_any_two()), "éß★x");
+        assert_eq!(table.priority_sum(rucksack.common_to_any_two()), 36);
+        let pairs = rucksack
+            .shared_pairs()
+            .into_iter()
+            .map(|(a, b, items)| (a, b, table.symbols_of(items)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                (0, 1, "éß".to_owned()),
+                (0, 2, "éx".to_owned()),
+                (1, 2, "é★".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_tables_and_rucksacks_are_rejected() {
+        assert_eq!(
+            PriorityTable::from_str("a 1\nb two\n"),
+            Err(ParsePriorityTableError::InvalidPriority {
+                line: 2,
+                token: "two".to_owned()
+            })
+        );
+        assert_eq!(
+            PriorityTable::from_str("a 1\na 2\n"),
+            Err(ParsePriorityTableError::DuplicateSymbol {
+                line: 2,
+                symbol: 'a'
+            })
+        );
+        assert_eq!(
+            PriorityTable::from_str("ab 1\n"),
+            Err(ParsePriorityTableError::Malformed { line: 1 })
+        );
+
+        let table = PriorityTable::standard();
+        assert_eq!(
+            Compartments::parse("abcde", 3, &table),
+            Err(CompartmentError::UnevenSplit {
+                len: 5,
+                compartments: 3
+            })
+        );
+        assert_eq!(
+            Compartments::parse("abé", 3, &table),
+            Err(CompartmentError::UnknownItem {
+                column: 3,
+                item: 'é'
+            })
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::score_byte;
+
+const PRIORITIES: usize = 53;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub swaps: usize,
+    pub rearranged: String,
+}
+
+// Work out the fewest swaps of one front item with one back item that leave
+// no item type in both compartments.
+//
+// Each item type ends up entirely in one compartment. Sending a type to the
+// back moves its front copies and sending it to the front moves its back
+// copies, and since swaps keep the compartments the same size, the types kept
+// in front must add up to exactly half the items. That makes this a subset sum
+// over at most 52 item types that minimises the number of items moved, and
+// every swap moves two items. Returns `None` when no split is possible, e.g.
+// `aaab`.
+//
+// `line` must be a valid rucksack, see `Rucksack::from_str`.
+pub fn plan(line: &str) -> Option<Plan> {
+    let bytes = line.as_bytes();
+    let half = bytes.len() / 2;
+    let (front, back) = bytes.split_at(half);
+
+    let mut front_counts = [0; PRIORITIES];
+    let mut back_counts = [0; PRIORITIES];
+    for &b in front {
+        front_counts[score_byte(b) as usize] += 1;
+    }
+    for &b in back {
+        back_counts[score_byte(b) as usize] += 1;
+    }
+    let types = (1..PRIORITIES)
+        .filter(|&p| front_counts[p] + back_counts[p] > 0)
+        .collect::<Vec<_>>();
+
+    // moved[i][w]: fewest items moved when the first `i` types put `w` items
+    // in the front compartment
+    let mut moved = vec![vec![None::<usize>; half + 1]; types.len() + 1];
+    moved[0][0] = Some(0);
+    for (i, &p) in types.iter().enumerate() {
+        let count = front_counts[p] + back_counts[p];
+        for w in 0..=half {
+            let Some(cost) = moved[i][w] else {
+                continue;
+            };
+            let to_back = cost + front_counts[p];
+            if moved[i + 1][w].is_none_or(|best| to_back < best) {
+                moved[i + 1][w] = Some(to_back);
+            }
+            if w + count <= half {
+                let to_front = cost + back_counts[p];
+                if moved[i + 1][w + count].is_none_or(|best| to_front < best) {
+                    moved[i + 1][w + count] = Some(to_front);
+                }
+            }
+        }
+    }
+    let total_moved = moved[types.len()][half]?;
+
+    // Walk the table backwards to recover which types stay in front
+    let mut keep_in_front = [false; PRIORITIES];
+    let mut w = half;
+    for (i, &p) in types.iter().enumerate().rev() {
+        let count = front_counts[p] + back_counts[p];
+        let cost = moved[i + 1][w].expect("Walking back along a reachable path");
+        let sent_back = cost
+            .checked_sub(front_counts[p])
+            .is_some_and(|previous| moved[i][w] == Some(previous));
+        if !sent_back {
+            keep_in_front[p] = true;
+            w -= count;
+        }
+    }
+
+    // Swap each out-of-place front item with an out-of-place back item, leaving
+    // everything else where it was
+    let mut rearranged = bytes.to_vec();
+    let misplaced_front = (0..half).filter(|&i| !keep_in_front[score_byte(bytes[i]) as usize]);
+    let misplaced_back =
+        (half..bytes.len()).filter(|&i| keep_in_front[score_byte(bytes[i]) as usize]);
+    let mut swaps = 0;
+    for (i, j) in misplaced_front.zip(misplaced_back) {
+        rearranged.swap(i, j);
+        swaps += 1;
+    }
+    debug_assert_eq!(swaps * 2, total_moved);
+
+    Some(Plan {
+        swaps,
+        rearranged: String::from_utf8(rearranged).expect("Items are ascii letters"),
+    })
+}
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::score_byte;
+
+// A set of items from a `PriorityTable`, as a bitmask of their indices in the
+// table. Only the table knows what an index means, so it has no priorities or
+// symbols of its own.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IndexSet(u64);
+
+impl IndexSet {
+    pub const CAPACITY: usize = u64::BITS as usize;
+
+    pub fn new() -> IndexSet {
+        IndexSet(0)
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        assert!(
+            index < IndexSet::CAPACITY,
+            "Item index {index} is out of range"
+        );
+        self.0 |= 1 << index;
+    }
+
+    pub fn intersection(self, other: IndexSet) -> IndexSet {
+        IndexSet(self.0 & other.0)
+    }
+
+    pub fn union(self, other: IndexSet) -> IndexSet {
+        IndexSet(self.0 | other.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // Indices in the set, lowest first
+    pub fn indices(&self) -> impl Iterator<Item = usize> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            (bits != 0).then(|| {
+                let index = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                index
+            })
+        })
+    }
+}
+
+impl FromIterator<usize> for IndexSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> IndexSet {
+        let mut set = IndexSet::new();
+        for index in iter {
+            set.insert(index);
+        }
+        set
+    }
+}
+
+// Maps item symbols, which may be any char, to their priorities. Every symbol
+// is also given an index so that sets of items fit in an `IndexSet`, which
+// limits a table to 64 symbols.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorityTable {
+    symbols: Vec<char>,
+    priorities: Vec<u32>,
+    indices: HashMap<char, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePriorityTableError {
+    // Lines are 1-based
+    Malformed { line: usize },
+    InvalidPriority { line: usize, token: String },
+    DuplicateSymbol { line: usize, symbol: char },
+    TooManySymbols,
+}
+
+impl fmt::Display for ParsePriorityTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePriorityTableError::Malformed { line } => {
+                write!(f, "line {line}: expected 'symbol priority'")
+            }
+            ParsePriorityTableError::InvalidPriority { line, token } => {
+                write!(f, "line {line}: '{token}' is not a valid priority")
+            }
+            ParsePriorityTableError::DuplicateSymbol { line, symbol } => {
+                write!(f, "line {line}: {symbol:?} already has a priority")
+            }
+            ParsePriorityTableError::TooManySymbols => {
+                write!(f, "a table can have at most {} symbols", IndexSet::CAPACITY)
+            }
+        }
+    }
+}
+
+impl PriorityTable {
+    fn new() -> PriorityTable {
+        PriorityTable {
+            symbols: Vec::new(),
+            priorities: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    // Returns false if the symbol is already in the table
+    fn push(&mut self, symbol: char, priority: u32) -> bool {
+        if self.indices.contains_key(&symbol) {
+            return false;
+        }
+        self.indices.insert(symbol, self.symbols.len());
+        self.symbols.push(symbol);
+        self.priorities.push(priority);
+        true
+    }
+
+    // The puzzle's own scheme: a-z are 1 to 26 and A-Z are 27 to 52
+    pub fn standard() -> PriorityTable {
+        let mut table = PriorityTable::new();
+        for byte in (b'a'..=b'z').chain(b'A'..=b'Z') {
+            table.push(byte as char, score_byte(byte));
+        }
+        table
+    }
+
+    pub fn index_of(&self, symbol: char) -> Option<usize> {
+        self.indices.get(&symbol).copied()
+    }
+
+    pub fn symbol(&self, index: usize) -> char {
+        self.symbols[index]
+    }
+
+    pub fn priority(&self, index: usize) -> u32 {
+        self.priorities[index]
+    }
+
+    pub fn priority_sum(&self, items: IndexSet) -> u32 {
+        items.indices().map(|i| self.priority(i)).sum()
+    }
+
+    pub fn symbols_of(&self, items: IndexSet) -> String {
+        items.indices().map(|i| self.symbol(i)).collect()
+    }
+}
+
+// One `symbol priority` pair per line, e.g. `é 53`. Blank lines are ignored.
+impl FromStr for PriorityTable {
+    type Err = ParsePriorityTableError;
+
+    fn from_str(s: &str) -> Result<PriorityTable, ParsePriorityTableError> {
+        let mut table = PriorityTable::new();
+        for (text, line) in s.lines().zip(1..) {
+            let mut tokens = text.split_whitespace();
+            let (symbol, priority) = match (tokens.next(), tokens.next(), tokens.next()) {
+                (None, _, _) => continue,
+                (Some(symbol), Some(priority), None) => (symbol, priority),
+                _ => return Err(ParsePriorityTableError::Malformed { line }),
+            };
+            let mut chars = symbol.chars();
+            let (Some(symbol), None) = (chars.next(), chars.next()) else {
+                return Err(ParsePriorityTableError::Malformed { line });
+            };
+            let priority =
+                priority
+                    .parse::<u32>()
+                    .map_err(|_| ParsePriorityTableError::InvalidPriority {
+                        line,
+                        token: priority.to_owned(),
+                    })?;
+            if table.symbols.len() == IndexSet::CAPACITY {
+                return Err(ParsePriorityTableError::TooManySymbols);
+            }
+            if !table.push(symbol, priority) {
+                return Err(ParsePriorityTableError::DuplicateSymbol { line, symbol });
+            }
+        }
+        Ok(table)
+    }
+}
@@ -0,0 +1,174 @@
+use std::{fmt, str::FromStr};
+
+use crate::score_byte;
+
+// A set of item types stored as a bitmask. Bit `n` is set when the item type
+// with priority `n` is present, so the 52 item types fit in a single u64.
+// Items looked up in a `PriorityTable` go in an `IndexSet` instead.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemSet(u64);
+
+impl ItemSet {
+    pub fn new() -> ItemSet {
+        ItemSet(0)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> ItemSet {
+        bytes.iter().fold(ItemSet::new(), |mut set, &byte| {
+            set.insert(byte);
+            set
+        })
+    }
+
+    pub fn insert(&mut self, item: u8) {
+        self.0 |= 1 << score_byte(item);
+    }
+
+    pub fn intersection(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    pub fn union(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 | other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // Priorities of the item types in the set, lowest first
+    pub fn priorities(&self) -> impl Iterator<Item = u32> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            (bits != 0).then(|| {
+                let priority = bits.trailing_zeros();
+                bits &= bits - 1;
+                priority
+            })
+        })
+    }
+
+    // Item types in the set, in priority order
+    pub fn items(&self) -> impl Iterator<Item = u8> {
+        self.priorities().map(item_for_priority)
+    }
+
+    pub fn priority_sum(&self) -> u32 {
+        self.priorities().sum()
+    }
+
+    // The priority of the only item type in the set
+    pub fn single_priority(&self) -> Option<u32> {
+        (self.len() == 1).then(|| self.0.trailing_zeros())
+    }
+}
+
+impl fmt::Display for ItemSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in self.items() {
+            write!(f, "{}", item as char)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<u8> for ItemSet {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> ItemSet {
+        let mut set = ItemSet::new();
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+fn item_for_priority(priority: u32) -> u8 {
+    match priority {
+        1..=26 => b'a' + (priority - 1) as u8,
+        27..=52 => b'A' + (priority - 27) as u8,
+        _ => panic!("{priority} is not a valid item priority"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rucksack {
+    front: ItemSet,
+    back: ItemSet,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRucksackError {
+    // The line can not be split into two equal compartments
+    OddLength(usize),
+    // `column` is 1-based and counts chars, not bytes
+    InvalidItem { column: usize, item: char },
+}
+
+impl fmt::Display for ParseRucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseRucksackError::OddLength(len) => {
+                write!(
+                    f,
+                    "odd number of items ({len}) can not fill two compartments"
+                )
+            }
+            ParseRucksackError::InvalidItem { column, item } => {
+                write!(
+                    f,
+                    "invalid item {item:?} at column {column}, expected a-z or A-Z"
+                )
+            }
+        }
+    }
+}
+
+impl FromStr for Rucksack {
+    type Err = ParseRucksackError;
+
+    fn from_str(line: &str) -> Result<Rucksack, ParseRucksackError> {
+        if let Some((i, item)) = line
+            .chars()
+            .enumerate()
+            .find(|(_, c)| !c.is_ascii_alphabetic())
+        {
+            return Err(ParseRucksackError::InvalidItem {
+                column: i + 1,
+                item,
+            });
+        }
+        let bytes = line.as_bytes();
+        if !bytes.len().is_multiple_of(2) {
+            return Err(ParseRucksackError::OddLength(bytes.len()));
+        }
+        let (front, back) = bytes.split_at(bytes.len() / 2);
+        Ok(Rucksack {
+            front: ItemSet::from_bytes(front),
+            back: ItemSet::from_bytes(back),
+        })
+    }
+}
+
+impl Rucksack {
+    // Item types found in both compartments
+    pub fn misplaced(&self) -> ItemSet {
+        self.front.intersection(self.back)
+    }
+
+    pub fn all_items(&self) -> ItemSet {
+        self.front.union(self.back)
+    }
+}
+
+// Item types carried by every rucksack in the group
+pub fn group_common(group: &[Rucksack]) -> ItemSet {
+    group
+        .iter()
+        .map(Rucksack::all_items)
+        .reduce(ItemSet::intersection)
+        .unwrap_or_default()
+}
@@ -0,0 +1,51 @@
+use std::fmt::Write;
+
+use crate::section::SectionRange;
+
+// Draw assignments the way the puzzle does, one row per assignment:
+//
+// .234.....  2-4
+// .....678.  6-8
+//
+// Every row uses the same `axis` so that rows line up. Sections wider than one
+// digit get cells as wide as the largest section, padded with dots.
+pub fn render(ranges: &[SectionRange], axis: &SectionRange) -> String {
+    let width = axis.end().to_string().len();
+    let mut output = String::new();
+    for range in ranges {
+        for section in axis.start()..=axis.end() {
+            if range.contains_section(section) {
+                write!(output, "{section:.>width$}").expect("Writing to a String never fails");
+            } else {
+                output.extend(std::iter::repeat_n('.', width));
+            }
+        }
+        writeln!(output, "  {range}").expect("Writing to a String never fails");
+    }
+    output
+}
+
+#[cfg(test)]
+mod diagram_tests {
+    use super::*;
+    use crate::test_support::range;
+
+    #[test]
+    fn matches_the_puzzle_diagram() {
+        let axis = range("1-9");
+        let drawing = render(&[range("2-4"), range("6-8")], &axis);
+        assert_eq!(drawing, ".234.....  2-4\n.....678.  6-8\n");
+    }
+
+    #[test]
+    fn single_section_ranges() {
+        let drawing = render(&[range("6-6"), range("4-6")], &range("1-9"));
+        assert_eq!(drawing, ".....6...  6-6\n...456...  4-6\n");
+    }
+
+    #[test]
+    fn wide_sections_get_wide_cells() {
+        let drawing = render(&[range("9-11")], &range("8-12"));
+        assert_eq!(drawing, "...91011..  9-11\n");
+    }
+}
@@ -0,0 +1,377 @@
+use std::fmt;
+
+use crate::section::SectionRange;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    // The shrunk assignments, in the same order as the originals
+    pub ranges: Vec<SectionRange>,
+    // Sections taken away from their original elf
+    pub reassigned: u64,
+}
+
+// Largest team the fixer will take on. The search keeps one state per subset
+// of elves, so it grows as 2^n.
+pub const MAX_TEAM_SIZE: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixError {
+    // More than `MAX_TEAM_SIZE` elves, so the team was not searched at all
+    TooLarge { elves: usize },
+    // Every way of shrinking the assignments leaves some elf with nothing
+    Unfixable,
+}
+
+impl fmt::Display for FixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixError::TooLarge { elves } => write!(
+                f,
+                "team of {elves} elves is too large to fix, at most {MAX_TEAM_SIZE} are supported"
+            ),
+            FixError::Unfixable => {
+                write!(f, "can not be fixed without leaving an elf unassigned")
+            }
+        }
+    }
+}
+
+// Sections between two consecutive starts or ends of assignments. Every
+// section of a run is in the same assignments.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start: u64,
+    len: u64,
+    // Bit `e` is set when elf `e`'s assignment covers the run
+    elves: usize,
+}
+
+fn runs(ranges: &[SectionRange]) -> Vec<Run> {
+    let mut bounds = ranges
+        .iter()
+        .flat_map(|r| [u64::from(r.start()), u64::from(r.end()) + 1])
+        .collect::<Vec<_>>();
+    bounds.sort_unstable();
+    bounds.dedup();
+    bounds
+        .windows(2)
+        .map(|pair| Run {
+            start: pair[0],
+            len: pair[1] - pair[0],
+            elves: ranges
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| u64::from(r.start()) <= pair[0] && pair[0] <= u64::from(r.end()))
+                .fold(0, |elves, (e, _)| elves | 1 << e),
+        })
+        .collect()
+}
+
+// Sections kept by going through `run` from state `from` to state `to`, or
+// `None` if that isn't possible. States are `(mask, active)` as in `fix`.
+fn run_gain(run: &Run, elves: usize, from: (usize, usize), to: (usize, usize)) -> Option<u64> {
+    let ((from_mask, from_active), (to_mask, to_active)) = (from, to);
+    let started = to_mask & !from_mask;
+    if from_mask & !to_mask != 0 || started & !run.elves != 0 {
+        return None;
+    }
+    if started == 0 {
+        return match to_active {
+            active if active == elves => Some(0),
+            active if active == from_active && run.elves & (1 << active) != 0 => Some(run.len),
+            _ => None,
+        };
+    }
+    (to_active < elves
+        && started & (1 << to_active) != 0
+        && u64::from(started.count_ones()) <= run.len)
+        .then_some(run.len)
+}
+
+// Shrink each assignment as little as possible so that no two assignments
+// share a section. Every elf keeps a non-empty, contiguous part of their
+// original assignment.
+//
+// Runs of sections are visited left to right. The state is the set of elves
+// that have kept something so far and which of them is still being extended;
+// an elf that stops can't start again, which keeps assignments contiguous.
+// Any gap left inside a run could be given to a neighbouring keeper, so a run
+// is either not kept at all or kept whole: by the active elf, by elves that
+// start there, or by the active elf and then new ones. Each keeper needs at
+// least one section. Memory and time depend on the team size, not on how
+// large the section ids are.
+//
+// Fails with `Unfixable` if there is no way to keep every elf assigned (e.g.
+// `3-3,3-3`) and with `TooLarge` for teams of more than `MAX_TEAM_SIZE`
+// elves, which are not searched.
+pub fn fix(ranges: &[SectionRange]) -> Result<Fix, FixError> {
+    let elves = ranges.len();
+    if elves == 0 {
+        return Ok(Fix {
+            ranges: Vec::new(),
+            reassigned: 0,
+        });
+    }
+    if elves > MAX_TEAM_SIZE {
+        return Err(FixError::TooLarge { elves });
+    }
+    let runs = runs(ranges);
+
+    // States are indexed by `mask * (elves + 1) + active`, where `active == elves`
+    // means no elf is being extended
+    let masks = 1usize << elves;
+    let states = masks * (elves + 1);
+    let index = |mask: usize, active: usize| mask * (elves + 1) + active;
+    let state_of = |state: usize| (state / (elves + 1), state % (elves + 1));
+
+    // kept[x][state]: most sections kept after deciding the first `x` runs
+    let mut kept = vec![vec![None::<u64>; states]; runs.len() + 1];
+    kept[0][index(0, elves)] = Some(0);
+    for (x, run) in runs.iter().enumerate() {
+        let (done, rest) = kept.split_at_mut(x + 1);
+        let (current, next) = (&done[x], &mut rest[0]);
+        let mut relax = |to: usize, value: u64| {
+            if next[to].is_none_or(|best| value > best) {
+                next[to] = Some(value);
+            }
+        };
+        for mask in 0..masks {
+            let mut best = None;
+            for active in 0..=elves {
+                let Some(so_far) = current[index(mask, active)] else {
+                    continue;
+                };
+                best = best.max(Some(so_far));
+                // Nobody keeps this run
+                relax(index(mask, elves), so_far);
+                // The active elf keeps going
+                if active < elves && run.elves & (1 << active) != 0 {
+                    relax(index(mask, active), so_far + run.len);
+                }
+            }
+            let Some(best) = best else {
+                continue;
+            };
+            // Elves that haven't kept anything yet start here, one after
+            // another, and the last of them is still active at the end
+            let fresh = run.elves & !mask;
+            let mut started = fresh;
+            while started != 0 {
+                if u64::from(started.count_ones()) <= run.len {
+                    for last in (0..elves).filter(|&e| started & (1 << e) != 0) {
+                        relax(index(mask | started, last), best + run.len);
+                    }
+                }
+                started = (started - 1) & fresh;
+            }
+        }
+    }
+
+    let full = masks - 1;
+    let (mut state, best) = (0..=elves)
+        .filter_map(|active| {
+            kept[runs.len()][index(full, active)].map(|k| (index(full, active), k))
+        })
+        .max_by_key(|&(_, k)| k)
+        .ok_or(FixError::Unfixable)?;
+
+    // Walk back through the runs to find where each elf starts and ends
+    let mut bounds = vec![(u64::MAX, 0); elves];
+    for (x, run) in runs.iter().enumerate().rev() {
+        let target = kept[x + 1][state];
+        let to = state_of(state);
+        let from = (0..states)
+            .find(|&s| {
+                kept[x][s]
+                    .zip(run_gain(run, elves, state_of(s), to))
+                    .map(|(k, g)| k + g)
+                    == target
+            })
+            .expect("Every reachable state has a state before it");
+        let ((from_mask, from_active), (to_mask, to_active)) = (state_of(from), to);
+        if run_gain(run, elves, state_of(from), to) != Some(0) {
+            let started = to_mask & !from_mask;
+            let mut keepers = Vec::new();
+            let continues = from_active < elves && run.elves & (1 << from_active) != 0;
+            if continues && (started == 0 || u64::from(started.count_ones()) < run.len) {
+                keepers.push(from_active);
+            }
+            keepers.extend((0..elves).filter(|&e| started & (1 << e) != 0 && e != to_active));
+            if started != 0 {
+                keepers.push(to_active);
+            }
+            // Every keeper after the first gets a single section
+            let mut end = run.start + run.len;
+            for (i, &elf) in keepers.iter().enumerate().rev() {
+                let start = if i == 0 { run.start } else { end - 1 };
+                let (low, high) = &mut bounds[elf];
+                *low = (*low).min(start);
+                *high = (*high).max(end - 1);
+                end = start;
+            }
+        }
+        state = from;
+    }
+    let fixed = bounds
+        .into_iter()
+        .map(|(start, end)| {
+            let section = |id| u32::try_from(id).expect("Kept sections come from the assignments");
+            SectionRange::new(section(start), section(end))
+                .expect("Every elf keeps at least one section")
+        })
+        .collect();
+    let original: u64 = ranges.iter().map(SectionRange::len).sum();
+    Ok(Fix {
+        ranges: fixed,
+        reassigned: original - best,
+    })
+}
+
+#[cfg(test)]
+mod fixer_tests {
+    use super::*;
+    use crate::test_support::ranges;
+
+    fn assert_disjoint(fixed: &[SectionRange]) {
+        for (i, a) in fixed.iter().enumerate() {
+            for b in &fixed[i + 1..] {
+                assert!(!a.overlaps(b), "{a} overlaps {b}");
+            }
+        }
+    }
+
+    // Most sections that can be kept, trying every way to shrink each range
+    fn brute_force_kept(ranges: &[SectionRange], chosen: &mut Vec<SectionRange>) -> Option<u64> {
+        let Some((range, rest)) = ranges.split_first() else {
+            return Some(chosen.iter().map(SectionRange::len).sum());
+        };
+        let mut best = None;
+        for start in range.start()..=range.end() {
+            for end in start..=range.end() {
+                let shrunk = SectionRange::new(start, end).unwrap();
+                if chosen.iter().any(|c| c.overlaps(&shrunk)) {
+                    continue;
+                }
+                chosen.push(shrunk);
+                best = best.max(brute_force_kept(rest, chosen));
+                chosen.pop();
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn disjoint_assignments_are_left_alone() {
+        let fix = fix(&ranges("2-4,6-8")).unwrap();
+        assert_eq!(fix.ranges, ranges("2-4,6-8"));
+        assert_eq!(fix.reassigned, 0);
+    }
+
+    #[test]
+    fn a_shared_section_goes_to_one_elf() {
+        let fix = fix(&ranges("5-7,7-9")).unwrap();
+        assert_eq!(fix.reassigned, 1);
+        assert_disjoint(&fix.ranges);
+    }
+
+    #[test]
+    fn a_nested_assignment_trims_the_outer_one_from_the_shorter_side() {
+        // Cutting 2-8 down to 5-8 loses 2, 3 and 4, which is less than
+        // cutting it down to 2-3
+        let fix = fix(&ranges("2-8,4-4")).unwrap();
+        assert_eq!(fix.ranges, ranges("5-8,4-4"));
+        assert_eq!(fix.reassigned, 3);
+    }
+
+    #[test]
+    fn identical_assignments_are_split() {
+        let fix = fix(&ranges("1-10,1-10")).unwrap();
+        assert_eq!(fix.reassigned, 10);
+        assert_disjoint(&fix.ranges);
+    }
+
+    #[test]
+    fn sample_fixes() {
+        let reassigned = include_str!("../sample_input.txt")
+            .lines()
+            .map(|line| fix(&ranges(line)).unwrap().reassigned)
+            .collect::<Vec<_>>();
+        // 2-8,3-7 keeps 3-7 and one side of 2-8, losing the other side
+        assert_eq!(reassigned, vec![0, 0, 1, 6, 1, 3]);
+    }
+
+    #[test]
+    fn larger_teams_are_fixed_too() {
+        let fix = fix(&ranges("1-10,3-4,6-6,9-12")).unwrap();
+        assert_disjoint(&fix.ranges);
+        // 1-10 has to fit between the others, so it keeps at most two
+        // sections: 1-2, 7-8 or, pushing 9-12 back to 11-12, 7-10
+        assert_eq!(fix.reassigned, 17 - 9);
+    }
+
+    #[test]
+    fn large_section_ids_cost_nothing_extra() {
+        let fix = fix(&ranges("1-400000000,5-5")).unwrap();
+        assert_eq!(fix.ranges, ranges("6-400000000,5-5"));
+        assert_eq!(fix.reassigned, 5);
+        let fix = super::fix(&ranges("0-4294967295,0-4294967295,7-7")).unwrap();
+        assert_disjoint(&fix.ranges);
+        assert_eq!(fix.reassigned, (1 << 32) + (1 << 32) + 1 - (1 << 32));
+    }
+
+    #[test]
+    fn short_runs_limit_how_many_elves_can_start() {
+        // 4-5 is covered by three elves but only two can start there
+        assert_eq!(fix(&ranges("4-5,4-5,4-5")), Err(FixError::Unfixable));
+        let fix = fix(&ranges("4-5,4-5,4-6")).unwrap();
+        assert_eq!(fix.reassigned, 7 - 3);
+        assert_disjoint(&fix.ranges);
+    }
+
+    #[test]
+    fn fixes_match_trying_every_shrink() {
+        let mut seed = 2022u32;
+        let mut next = |bound: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % bound
+        };
+        for _ in 0..300 {
+            let team = (0..2 + next(3))
+                .map(|_| {
+                    let start = 1 + next(8);
+                    SectionRange::new(start, start + next(4)).unwrap()
+                })
+                .collect::<Vec<_>>();
+            let original: u64 = team.iter().map(SectionRange::len).sum();
+            let expected = brute_force_kept(&team, &mut Vec::new());
+            let found = fix(&team).ok();
+            assert_eq!(
+                found.as_ref().map(|f| original - f.reassigned),
+                expected,
+                "{team:?}"
+            );
+            if let Some(found) = found {
+                assert_disjoint(&found.ranges);
+                for (fixed, range) in found.ranges.iter().zip(&team) {
+                    assert!(range.contains(fixed), "{fixed} is not in {range}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn impossible_fixes_are_reported() {
+        assert_eq!(fix(&ranges("3-3,3-3")), Err(FixError::Unfixable));
+        assert_eq!(fix(&ranges("1-2,1-2,2-2")), Err(FixError::Unfixable));
+    }
+
+    #[test]
+    fn teams_over_the_limit_are_not_searched() {
+        // Only the first two elves need fixing, but the team is too large
+        let team = (3..=13).fold("1-2,1-2".to_owned(), |team, id| format!("{team},{id}-{id}"));
+        assert_eq!(fix(&ranges(&team)), Err(FixError::TooLarge { elves: 13 }));
+        let mut team = ranges(&team);
+        team.pop();
+        assert_eq!(fix(&team).unwrap().reassigned, 2);
+    }
+}
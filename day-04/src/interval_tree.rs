@@ -0,0 +1,139 @@
+use crate::{section::SectionRange, sweep::Assignment};
+
+// A static, augmented interval tree. Assignments are sorted by start and the
+// sorted array is read as a balanced binary search tree: the middle element of
+// each slice is that subtree's root. Each node also records the largest end
+// in its subtree, so whole subtrees that finish before a query can be skipped.
+#[derive(Debug, Clone)]
+pub struct IntervalTree {
+    assignments: Vec<Assignment>,
+    max_end: Vec<u32>,
+}
+
+impl IntervalTree {
+    pub fn new(assignments: &[Assignment]) -> IntervalTree {
+        let mut assignments = assignments.to_vec();
+        assignments.sort_by_key(|a| (a.range.start(), a.range.end(), a.elf));
+        let mut max_end = vec![0; assignments.len()];
+        build_max_end(&assignments, &mut max_end, 0, assignments.len());
+        IntervalTree {
+            assignments,
+            max_end,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.assignments.len()
+    }
+
+    // Assignments that include `section`
+    pub fn stab(&self, section: u32) -> Vec<&Assignment> {
+        let point = SectionRange::new(section, section).expect("A single section is a range");
+        self.overlapping(&point)
+    }
+
+    // Assignments that share at least one section with `query`, in order of
+    // assignment start
+    pub fn overlapping(&self, query: &SectionRange) -> Vec<&Assignment> {
+        let mut found = Vec::new();
+        self.collect(query, 0, self.assignments.len(), &mut found);
+        found
+    }
+
+    fn collect<'a>(
+        &'a self,
+        query: &SectionRange,
+        lo: usize,
+        hi: usize,
+        found: &mut Vec<&'a Assignment>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        // Nothing in this subtree reaches the query
+        if self.max_end[mid] < query.start() {
+            return;
+        }
+        self.collect(query, lo, mid, found);
+        let node = &self.assignments[mid];
+        if node.range.overlaps(query) {
+            found.push(node);
+        }
+        // Everything to the right starts at or after this node
+        if node.range.start() <= query.end() {
+            self.collect(query, mid + 1, hi, found);
+        }
+    }
+}
+
+fn build_max_end(assignments: &[Assignment], max_end: &mut [u32], lo: usize, hi: usize) -> u32 {
+    if lo >= hi {
+        return 0;
+    }
+    let mid = lo + (hi - lo) / 2;
+    let left = build_max_end(assignments, max_end, lo, mid);
+    let right = build_max_end(assignments, max_end, mid + 1, hi);
+    max_end[mid] = assignments[mid].range.end().max(left).max(right);
+    max_end[mid]
+}
+
+#[cfg(test)]
+mod interval_tree_tests {
+    use super::*;
+    use crate::test_support::{assignments, range};
+
+    fn tree(ranges: &[&str]) -> IntervalTree {
+        IntervalTree::new(&assignments(ranges))
+    }
+
+    fn lines(found: Vec<&Assignment>) -> Vec<usize> {
+        let mut lines = found.iter().map(|a| a.elf.line).collect::<Vec<_>>();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn point_queries_find_every_covering_assignment() {
+        let t = tree(&["2-4", "6-8", "2-3", "4-5", "5-7", "2-8", "6-6"]);
+        assert_eq!(lines(t.stab(1)), vec![]);
+        assert_eq!(lines(t.stab(4)), vec![1, 4, 6]);
+        assert_eq!(lines(t.stab(6)), vec![2, 5, 6, 7]);
+        assert_eq!(lines(t.stab(9)), vec![]);
+    }
+
+    #[test]
+    fn range_queries_include_touching_edges() {
+        let t = tree(&["2-4", "6-8", "2-3", "4-5", "5-7", "2-8", "6-6"]);
+        let query = range("5-5");
+        assert_eq!(lines(t.overlapping(&query)), vec![4, 5, 6]);
+        let query = range("8-20");
+        assert_eq!(lines(t.overlapping(&query)), vec![2, 6]);
+    }
+
+    #[test]
+    fn queries_agree_with_a_linear_scan() {
+        let ranges = (0..200)
+            .map(|i: u32| format!("{}-{}", (i * 37) % 100, (i * 37) % 100 + (i * 13) % 20))
+            .collect::<Vec<_>>();
+        let refs = ranges.iter().map(String::as_str).collect::<Vec<_>>();
+        let t = tree(&refs);
+        for start in (0..130).step_by(7) {
+            let query = SectionRange::new(start, start + 5).unwrap();
+            let expected = ranges
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| range(r).overlaps(&query))
+                .map(|(i, _)| i + 1)
+                .collect::<Vec<_>>();
+            assert_eq!(lines(t.overlapping(&query)), expected);
+        }
+    }
+
+    #[test]
+    fn an_empty_tree_finds_nothing() {
+        let t = tree(&[]);
+        assert_eq!(t.len(), 0);
+        assert!(t.stab(3).is_empty());
+    }
+}
@@ -1,30 +1,291 @@
-use std::{fs, io, str::FromStr};
+use std::{
+    env, fs,
+    io::{self, BufRead},
+    str::FromStr,
+};
+
+mod diagram;
+mod fixer;
+mod interval_tree;
+mod section;
+mod sweep;
+mod team;
+#[cfg(test)]
+mod test_support;
+
+use fixer::FixError;
+use interval_tree::IntervalTree;
+use section::SectionRange;
+use sweep::{Assignment, Elf, PairKind};
+use team::Team;
+
+fn parse_teams(input: &str) -> Vec<Team> {
+    input
+        .lines()
+        .zip(1..)
+        .map(|(line, n)| {
+            Team::from_str(line).unwrap_or_else(|e| panic!("Malformed line {n} '{line}': {e}"))
+        })
+        .collect()
+}
+
+// Every elf's assignment, in input order
+fn assignments(teams: &[Team]) -> Vec<Assignment> {
+    teams
+        .iter()
+        .zip(1..)
+        .flat_map(|(team, line)| {
+            team.ranges()
+                .iter()
+                .enumerate()
+                .map(move |(position, &range)| Assignment {
+                    elf: Elf { line, position },
+                    range,
+                })
+        })
+        .collect()
+}
+
+fn part_1(teams: &[Team]) -> usize {
+    teams
+        .iter()
+        .filter(|team| team.covering_assignment().is_some())
+        .count()
+}
+
+fn part_2(teams: &[Team]) -> usize {
+    teams.iter().filter(|team| team.has_overlap()).count()
+}
+
+// Per-team summary for lines with any number of assignments
+fn run_teams(teams: &[Team]) {
+    for (team, line) in teams.iter().zip(1..) {
+        let covering = match team.covering_assignment() {
+            Some(position) => format!("elf {} covers the team", position + 1),
+            None => "no covering elf".to_owned(),
+        };
+        let overlap = if team.has_overlap() {
+            "overlap"
+        } else {
+            "no overlap"
+        };
+        let coverage = team.coverage();
+        println!(
+            "line {line}: {} elves, {covering}, {overlap}, covers {} sections [{coverage}]",
+            team.ranges().len(),
+            coverage.len()
+        );
+    }
+    println!("day-04;teams covered = {}", part_1(teams));
+    println!("day-04;teams overlapping = {}", part_2(teams));
+}
+
+// Compare every elf against every other elf in the file, not just its partner
+fn run_sweep(teams: &[Team], list_pairs: bool) {
+    let report = sweep::sweep(&assignments(teams));
+    if list_pairs {
+        for pair in &report.pairs {
+            let kind = match pair.kind {
+                PairKind::Nested => "nested",
+                PairKind::Overlap => "overlap",
+            };
+            println!("{} {} {kind}", pair.first, pair.second);
+        }
+    }
+    let nested = report
+        .pairs
+        .iter()
+        .filter(|p| p.kind == PairKind::Nested)
+        .count();
+    println!(
+        "day-04;sweep overlapping pairs = {} ({nested} nested)",
+        report.pairs.len()
+    );
+    match report.busiest_section {
+        Some(section) => println!(
+            "day-04;sweep max elves per section = {} (first at section {section})",
+            report.max_elves
+        ),
+        None => println!("day-04;sweep max elves per section = 0"),
+    }
+    println!(
+        "day-04;sweep uncovered sections = {} [{}]",
+        report.uncovered.len(),
+        report.uncovered
+    );
+}
+
+// Answer coverage queries read from stdin, one per line: a section id such as
+// `57` lists the elves assigned to that section and a range such as `20-40`
+// lists the assignments that intersect it
+fn run_queries(teams: &[Team]) -> io::Result<()> {
+    let tree = IntervalTree::new(&assignments(teams));
+    eprintln!("day-04;query indexed {} assignments", tree.len());
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let query = line.trim();
+        if query.is_empty() {
+            continue;
+        }
+        let found = if let Ok(section) = query.parse::<u32>() {
+            tree.stab(section)
+        } else {
+            match SectionRange::from_str(query) {
+                Ok(range) => tree.overlapping(&range),
+                Err(e) => {
+                    println!("{query}: {e}");
+                    continue;
+                }
+            }
+        };
+        let elves = found
+            .iter()
+            .map(|a| format!("{}({})", a.elf, a.range))
+            .collect::<Vec<_>>();
+        println!("{query}: {} [{}]", found.len(), elves.join(" "));
+    }
+    Ok(())
+}
+
+// One axis for the whole file, starting at section 1 like the puzzle's
+// diagrams
+fn shared_axis(teams: &[Team]) -> SectionRange {
+    let last = teams
+        .iter()
+        .flat_map(Team::ranges)
+        .map(SectionRange::end)
+        .max()
+        .unwrap_or(1);
+    SectionRange::new(1.min(last), last).expect("Axis starts at or before its end")
+}
+
+fn run_draw(teams: &[Team]) {
+    let axis = shared_axis(teams);
+    for team in teams {
+        println!("{}", diagram::render(team.ranges(), &axis));
+    }
+}
+
+// Propose the smallest change to every team so that no assignments overlap
+fn run_fix(teams: &[Team]) {
+    let axis = shared_axis(teams);
+    let mut total_reassigned = 0;
+    let mut unfixable = 0;
+    let mut too_large = 0;
+    for (team, line) in teams.iter().zip(1..) {
+        if !team.has_overlap() {
+            continue;
+        }
+        match fixer::fix(team.ranges()) {
+            Ok(fix) => {
+                println!("line {line}: {} sections reassigned", fix.reassigned);
+                print!("{}", diagram::render(team.ranges(), &axis));
+                println!("=>");
+                println!("{}", diagram::render(&fix.ranges, &axis));
+                total_reassigned += fix.reassigned;
+            }
+            Err(error @ FixError::TooLarge { .. }) => {
+                println!("line {line}: skipped, {error}\n");
+                too_large += 1;
+            }
+            Err(error @ FixError::Unfixable) => {
+                println!("line {line}: {error}\n");
+                unfixable += 1;
+            }
+        }
+    }
+    println!("day-04;fix sections reassigned = {total_reassigned}");
+    println!("day-04;fix unfixable teams = {unfixable}");
+    println!("day-04;fix skipped teams = {too_large}");
+}
 
 fn main() -> io::Result<()> {
     const FILE_PATH: &str = "input.txt";
     let input = fs::read_to_string(FILE_PATH)?;
+    let teams = parse_teams(&input);
 
-    let mut total_overlap_count = 0;
-    let mut any_overlap_count = 0;
-    for line in input.lines() {
-        let (range_1, range_2) = line.split_once(',').expect("Each line has a comma");
-        let (str_min_1, str_max_1) = range_1.split_once('-').expect("Each range has a dash");
-        let (str_min_2, str_max_2) = range_2.split_once('-').expect("Each range has a dash");
-        let min_1 = u32::from_str(str_min_1).expect("Token is a valid u32");
-        let max_1 = u32::from_str(str_max_1).expect("Token is a valid u32");
-        let min_2 = u32::from_str(str_min_2).expect("Token is a valid u32");
-        let max_2 = u32::from_str(str_max_2).expect("Token is a valid u32");
-
-        if (min_1 <= min_2 && max_1 >= max_2) || (min_2 <= min_1 && max_2 >= max_1) {
-            total_overlap_count += 1;
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("sweep") => {
+            let list_pairs = args.iter().any(|a| a == "--pairs");
+            run_sweep(&teams, list_pairs);
+            return Ok(());
         }
-
-        if (min_1 <= min_2 && max_1 >= min_2) || (min_2 <= min_1 && max_2 >= min_1) {
-            any_overlap_count += 1;
+        Some("query") => return run_queries(&teams),
+        Some("draw") => {
+            run_draw(&teams);
+            return Ok(());
         }
+        Some("fix") => {
+            run_fix(&teams);
+            return Ok(());
+        }
+        Some("teams") => {
+            run_teams(&teams);
+            return Ok(());
+        }
+        _ => {}
     }
 
-    println!("day-04;part-1 = {}", total_overlap_count);
-    println!("day-04;part-2 = {}", any_overlap_count);
+    println!("day-04;part-1 = {}", part_1(&teams));
+    println!("day-04;part-2 = {}", part_2(&teams));
     Ok(())
 }
+
+#[cfg(test)]
+mod day_04_tests {
+    use crate::{parse_teams, part_1, part_2, team::Team};
+    use std::str::FromStr;
+
+    const SAMPLE: &str = include_str!("../sample_input.txt");
+
+    fn team(s: &str) -> Team {
+        Team::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn sample_part_1() {
+        assert_eq!(part_1(&parse_teams(SAMPLE)), 2);
+    }
+
+    #[test]
+    fn sample_part_2() {
+        assert_eq!(part_2(&parse_teams(SAMPLE)), 4);
+    }
+
+    #[test]
+    fn a_team_can_have_any_number_of_elves() {
+        assert_eq!(team("1-2").ranges().len(), 1);
+        assert_eq!(team("1-2,3-4,5-6,7-8").ranges().len(), 4);
+    }
+
+    #[test]
+    fn a_malformed_range_is_reported_by_position() {
+        let error = Team::from_str("1-2,3-4,5").unwrap_err();
+        assert_eq!(error.position, 2);
+        assert!(Team::from_str("").is_err());
+    }
+
+    #[test]
+    fn the_covering_elf_starts_first_and_ends_last() {
+        assert_eq!(team("3-5,1-9,2-4").covering_assignment(), Some(1));
+        assert_eq!(team("1-5,2-9,1-4").covering_assignment(), None);
+        assert_eq!(team("4-4").covering_assignment(), Some(0));
+    }
+
+    #[test]
+    fn overlap_between_any_two_members() {
+        assert!(!team("1-2,4-5,7-8").has_overlap());
+        assert!(team("1-2,7-8,4-7").has_overlap());
+        // The overlap is with an earlier, longer range, not the neighbour
+        assert!(team("1-10,3-4,6-6").has_overlap());
+        assert!(!team("1-2,3-4").has_overlap());
+    }
+
+    #[test]
+    fn team_coverage_merges_touching_ranges() {
+        let coverage = team("5-6,1-2,3-4,9-9").coverage();
+        assert_eq!(coverage.to_string(), "1-6,9-9");
+        assert_eq!(coverage.len(), 7);
+    }
+}
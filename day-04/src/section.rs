@@ -0,0 +1,360 @@
+use std::{fmt, num::ParseIntError, str::FromStr};
+
+// An inclusive range of section IDs, e.g. `2-4` is sections 2, 3 and 4. A
+// range always has at least one section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SectionRange {
+    start: u32,
+    end: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSectionRangeError {
+    MissingDash,
+    InvalidBound(ParseIntError),
+    Reversed { start: u32, end: u32 },
+}
+
+impl fmt::Display for ParseSectionRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSectionRangeError::MissingDash => write!(f, "range has no '-'"),
+            ParseSectionRangeError::InvalidBound(e) => write!(f, "invalid section id: {e}"),
+            ParseSectionRangeError::Reversed { start, end } => {
+                write!(f, "range {start}-{end} ends before it starts")
+            }
+        }
+    }
+}
+
+impl From<ParseIntError> for ParseSectionRangeError {
+    fn from(e: ParseIntError) -> Self {
+        ParseSectionRangeError::InvalidBound(e)
+    }
+}
+
+impl FromStr for SectionRange {
+    type Err = ParseSectionRangeError;
+
+    fn from_str(s: &str) -> Result<SectionRange, ParseSectionRangeError> {
+        let (start, end) = s
+            .trim()
+            .split_once('-')
+            .ok_or(ParseSectionRangeError::MissingDash)?;
+        let start = u32::from_str(start)?;
+        let end = u32::from_str(end)?;
+        SectionRange::new(start, end).ok_or(ParseSectionRangeError::Reversed { start, end })
+    }
+}
+
+impl fmt::Display for SectionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl SectionRange {
+    pub fn new(start: u32, end: u32) -> Option<SectionRange> {
+        (start <= end).then_some(SectionRange { start, end })
+    }
+
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    pub fn end(&self) -> u32 {
+        self.end
+    }
+
+    // Number of sections in the range. `0-4294967295` has one more section
+    // than fits in a u32.
+    pub fn len(&self) -> u64 {
+        u64::from(self.end - self.start) + 1
+    }
+
+    pub fn contains_section(&self, section: u32) -> bool {
+        self.start <= section && section <= self.end
+    }
+
+    // True if every section of `other` is also in `self`
+    pub fn contains(&self, other: &SectionRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &SectionRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn intersection(&self, other: &SectionRange) -> Option<SectionRange> {
+        SectionRange::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn union(&self, other: &SectionRange) -> IntervalSet {
+        IntervalSet::from_iter([*self, *other])
+    }
+
+    // Sections of `self` that are not in `other`
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn difference(&self, other: &SectionRange) -> IntervalSet {
+        if !self.overlaps(other) {
+            return IntervalSet::from_iter([*self]);
+        }
+        let before = (self.start < other.start)
+            .then(|| SectionRange::new(self.start, other.start - 1))
+            .flatten();
+        let after = (other.end < self.end)
+            .then(|| SectionRange::new(other.end + 1, self.end))
+            .flatten();
+        before.into_iter().chain(after).collect()
+    }
+
+    // Ranges that overlap or are next to each other, like 2-4 and 5-6, can be
+    // joined into one
+    fn joins(&self, other: &SectionRange) -> bool {
+        self.start <= other.end.saturating_add(1) && other.start <= self.end.saturating_add(1)
+    }
+}
+
+// A set of sections stored as sorted, disjoint ranges. Ranges that overlap or
+// touch are merged as they are inserted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<SectionRange>,
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet { ranges: Vec::new() }
+    }
+
+    pub fn insert(&mut self, range: SectionRange) {
+        // First range that could join `range`; everything before it ends too
+        // early to touch it
+        let first = self
+            .ranges
+            .partition_point(|r| r.end.saturating_add(1) < range.start);
+        let mut merged = range;
+        let mut last = first;
+        while last < self.ranges.len() && self.ranges[last].joins(&merged) {
+            merged = SectionRange {
+                start: merged.start.min(self.ranges[last].start),
+                end: merged.end.max(self.ranges[last].end),
+            };
+            last += 1;
+        }
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn ranges(&self) -> &[SectionRange] {
+        &self.ranges
+    }
+
+    // Number of distinct sections in the set
+    pub fn len(&self) -> u64 {
+        self.ranges.iter().map(SectionRange::len).sum()
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn contains_section(&self, section: u32) -> bool {
+        let i = self.ranges.partition_point(|r| r.end < section);
+        self.ranges
+            .get(i)
+            .is_some_and(|r| r.contains_section(section))
+    }
+}
+
+impl FromIterator<SectionRange> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = SectionRange>>(iter: I) -> IntervalSet {
+        let mut set = IntervalSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl fmt::Display for IntervalSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(SectionRange::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}", ranges.join(","))
+    }
+}
+
+#[cfg(test)]
+mod section_tests {
+    use super::*;
+    use crate::test_support::range;
+
+    fn set(ranges: &[&str]) -> IntervalSet {
+        ranges.iter().map(|s| range(s)).collect()
+    }
+
+    mod parsing {
+        use super::*;
+
+        #[test]
+        fn a_range_has_a_start_and_an_end() {
+            let r = range("2-4");
+            assert_eq!((r.start(), r.end()), (2, 4));
+        }
+
+        #[test]
+        fn a_single_section_range() {
+            assert_eq!(range("6-6").len(), 1);
+        }
+
+        #[test]
+        fn malformed_ranges_are_rejected() {
+            assert_eq!(
+                SectionRange::from_str("24"),
+                Err(ParseSectionRangeError::MissingDash)
+            );
+            assert!(matches!(
+                SectionRange::from_str("2-x"),
+                Err(ParseSectionRangeError::InvalidBound(_))
+            ));
+            assert_eq!(
+                SectionRange::from_str("4-2"),
+                Err(ParseSectionRangeError::Reversed { start: 4, end: 2 })
+            );
+        }
+
+        #[test]
+        fn display_round_trips() {
+            assert_eq!(range("12-345").to_string(), "12-345");
+        }
+    }
+
+    mod comparisons {
+        use super::*;
+
+        #[test]
+        fn a_range_contains_itself() {
+            assert!(range("3-7").contains(&range("3-7")));
+        }
+
+        #[test]
+        fn contains_is_not_symmetric() {
+            assert!(range("2-8").contains(&range("3-7")));
+            assert!(!range("3-7").contains(&range("2-8")));
+        }
+
+        #[test]
+        fn a_range_contains_a_single_section_at_its_edge() {
+            assert!(range("4-6").contains(&range("6-6")));
+            assert!(range("4-6").contains(&range("4-4")));
+        }
+
+        #[test]
+        fn ranges_sharing_one_section_overlap() {
+            assert!(range("5-7").overlaps(&range("7-9")));
+            assert!(range("7-9").overlaps(&range("5-7")));
+        }
+
+        #[test]
+        fn touching_ranges_do_not_overlap() {
+            assert!(!range("2-4").overlaps(&range("5-6")));
+            assert!(!range("5-6").overlaps(&range("2-4")));
+        }
+    }
+
+    mod set_algebra {
+        use super::*;
+
+        #[test]
+        fn intersection_of_overlapping_ranges() {
+            assert_eq!(range("2-6").intersection(&range("4-8")), Some(range("4-6")));
+            assert_eq!(range("5-7").intersection(&range("7-9")), Some(range("7-7")));
+        }
+
+        #[test]
+        fn intersection_of_disjoint_ranges_is_none() {
+            assert_eq!(range("2-4").intersection(&range("5-6")), None);
+        }
+
+        #[test]
+        fn union_of_touching_ranges_is_one_range() {
+            assert_eq!(range("2-4").union(&range("5-6")), set(&["2-6"]));
+        }
+
+        #[test]
+        fn union_of_separate_ranges_keeps_both() {
+            let union = range("6-8").union(&range("2-4"));
+            assert_eq!(union.ranges(), &[range("2-4"), range("6-8")]);
+            assert_eq!(union.len(), 6);
+        }
+
+        #[test]
+        fn difference_can_split_a_range() {
+            assert_eq!(range("2-8").difference(&range("4-5")), set(&["2-3", "6-8"]));
+        }
+
+        #[test]
+        fn difference_at_the_edges() {
+            assert_eq!(range("2-8").difference(&range("2-5")), set(&["6-8"]));
+            assert_eq!(range("2-8").difference(&range("5-9")), set(&["2-4"]));
+            assert_eq!(range("2-8").difference(&range("9-9")), set(&["2-8"]));
+        }
+
+        #[test]
+        fn difference_with_a_covering_range_is_empty() {
+            assert!(range("3-3").difference(&range("1-9")).is_empty());
+        }
+
+        #[test]
+        fn len_counts_sections_inclusively() {
+            assert_eq!(range("2-4").len(), 3);
+            assert_eq!(range("0-0").len(), 1);
+            assert_eq!(range("0-4294967295").len(), 1 << 32);
+        }
+    }
+
+    mod interval_set {
+        use super::*;
+
+        #[test]
+        fn inserting_a_bridging_range_merges_its_neighbours() {
+            let merged = set(&["1-2", "8-9", "4-5", "3-7"]);
+            assert_eq!(merged, set(&["1-9"]));
+        }
+
+        #[test]
+        fn ranges_are_kept_sorted_and_disjoint() {
+            let s = set(&["20-30", "1-3", "10-12", "11-15"]);
+            assert_eq!(s.to_string(), "1-3,10-15,20-30");
+            assert_eq!(s.len(), 3 + 6 + 11);
+        }
+
+        #[test]
+        fn section_membership() {
+            let s = set(&["1-3", "10-15"]);
+            assert!(s.contains_section(1));
+            assert!(s.contains_section(15));
+            assert!(!s.contains_section(4));
+            assert!(!s.contains_section(16));
+        }
+
+        #[test]
+        fn sections_at_the_top_of_the_range() {
+            let s = set(&[&format!("{}-{}", u32::MAX - 1, u32::MAX), "0-0"]);
+            assert_eq!(s.len(), 3);
+        }
+
+        #[test]
+        fn every_section_id_fits_in_len() {
+            let s = set(&["0-10", &format!("5-{}", u32::MAX)]);
+            assert_eq!(s.len(), 1 << 32);
+        }
+    }
+}
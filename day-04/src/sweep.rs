@@ -0,0 +1,198 @@
+use std::{cmp::Reverse, collections::BinaryHeap, fmt};
+
+use crate::section::{IntervalSet, SectionRange};
+
+// An elf is identified by its line in the input (1-based) and its position on
+// that line (0-based)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Elf {
+    pub line: usize,
+    pub position: usize,
+}
+
+impl fmt::Display for Elf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.position + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assignment {
+    pub elf: Elf,
+    pub range: SectionRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairKind {
+    // One assignment contains the other
+    Nested,
+    Overlap,
+}
+
+// `first` is always the elf whose assignment starts first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlapPair {
+    pub first: Elf,
+    pub second: Elf,
+    pub kind: PairKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SweepReport {
+    pub pairs: Vec<OverlapPair>,
+    // Most elves assigned to any single section, and the lowest section
+    // where that happens
+    pub max_elves: usize,
+    pub busiest_section: Option<u32>,
+    // Sections between the lowest and highest assigned section that nobody
+    // is assigned to
+    pub uncovered: IntervalSet,
+}
+
+// Sweep across the sections once, in order of assignment start. Assignments
+// still active when a new one starts are exactly the ones that overlap it, so
+// this runs in O(n log n + k) for n assignments and k overlapping pairs.
+pub fn sweep(assignments: &[Assignment]) -> SweepReport {
+    let mut order = (0..assignments.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| {
+        (
+            assignments[i].range.start(),
+            Reverse(assignments[i].range.end()),
+        )
+    });
+
+    let mut pairs = Vec::new();
+    let mut active = BinaryHeap::<Reverse<(u32, usize)>>::new();
+    let mut max_elves = 0;
+    let mut busiest_section = None;
+    for &i in &order {
+        let current = &assignments[i];
+        while let Some(&Reverse((end, _))) = active.peek() {
+            if end >= current.range.start() {
+                break;
+            }
+            active.pop();
+        }
+        for &Reverse((_, j)) in active.iter() {
+            let earlier = &assignments[j];
+            let kind = if earlier.range.contains(&current.range)
+                || current.range.contains(&earlier.range)
+            {
+                PairKind::Nested
+            } else {
+                PairKind::Overlap
+            };
+            pairs.push(OverlapPair {
+                first: earlier.elf,
+                second: current.elf,
+                kind,
+            });
+        }
+        active.push(Reverse((current.range.end(), i)));
+        // Every active assignment covers the start of the current one
+        if active.len() > max_elves {
+            max_elves = active.len();
+            busiest_section = Some(current.range.start());
+        }
+    }
+
+    let covered = assignments.iter().map(|a| a.range).collect::<IntervalSet>();
+    let uncovered = covered
+        .ranges()
+        .windows(2)
+        .filter_map(|w| SectionRange::new(w[0].end() + 1, w[1].start() - 1))
+        .collect();
+
+    pairs.sort_by_key(|p| (p.first, p.second));
+    SweepReport {
+        pairs,
+        max_elves,
+        busiest_section,
+        uncovered,
+    }
+}
+
+#[cfg(test)]
+mod sweep_tests {
+    use super::*;
+    use crate::test_support::assignments;
+
+    fn elf(line: usize) -> Elf {
+        Elf { line, position: 0 }
+    }
+
+    // Check the sweep against comparing every pair directly
+    fn brute_force_pairs(assignments: &[Assignment]) -> usize {
+        let mut count = 0;
+        for (i, a) in assignments.iter().enumerate() {
+            for b in &assignments[i + 1..] {
+                if a.range.overlaps(&b.range) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn overlapping_and_nested_pairs_are_reported() {
+        let report = sweep(&assignments(&["2-8", "3-7", "6-9", "10-12"]));
+        assert_eq!(
+            report.pairs,
+            vec![
+                OverlapPair {
+                    first: elf(1),
+                    second: elf(2),
+                    kind: PairKind::Nested
+                },
+                OverlapPair {
+                    first: elf(1),
+                    second: elf(3),
+                    kind: PairKind::Overlap
+                },
+                OverlapPair {
+                    first: elf(2),
+                    second: elf(3),
+                    kind: PairKind::Overlap
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn touching_assignments_do_not_overlap_and_leave_no_gap() {
+        let report = sweep(&assignments(&["2-4", "5-6"]));
+        assert!(report.pairs.is_empty());
+        assert!(report.uncovered.is_empty());
+        assert_eq!(report.max_elves, 1);
+    }
+
+    #[test]
+    fn busiest_section_counts_every_assignment_covering_it() {
+        let report = sweep(&assignments(&["1-5", "3-9", "5-5", "6-8"]));
+        assert_eq!(report.max_elves, 3);
+        assert_eq!(report.busiest_section, Some(5));
+    }
+
+    #[test]
+    fn gaps_between_assignments_are_uncovered() {
+        let report = sweep(&assignments(&["1-2", "10-12", "4-5", "5-6"]));
+        assert_eq!(report.uncovered.to_string(), "3-3,7-9");
+    }
+
+    #[test]
+    fn sample_has_as_many_pairs_as_brute_force() {
+        let input = include_str!("../sample_input.txt");
+        let all = crate::assignments(&crate::parse_teams(input));
+        let report = sweep(&all);
+        assert_eq!(report.pairs.len(), brute_force_pairs(&all));
+    }
+
+    #[test]
+    fn no_assignments() {
+        let report = sweep(&[]);
+        assert_eq!(report.max_elves, 0);
+        assert_eq!(report.busiest_section, None);
+        assert!(report.uncovered.is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use std::{fmt, str::FromStr};
+
+use crate::section::{IntervalSet, ParseSectionRangeError, SectionRange};
+
+// The assignments on one line of the input, one per elf in the team
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Team {
+    ranges: Vec<SectionRange>,
+}
+
+// `position` is the 0-based index of the bad range on the line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTeamError {
+    pub position: usize,
+    pub error: ParseSectionRangeError,
+}
+
+impl fmt::Display for ParseTeamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "range {}: {}", self.position + 1, self.error)
+    }
+}
+
+impl FromStr for Team {
+    type Err = ParseTeamError;
+
+    fn from_str(s: &str) -> Result<Team, ParseTeamError> {
+        let ranges = s
+            .split(',')
+            .enumerate()
+            .map(|(position, range)| {
+                SectionRange::from_str(range).map_err(|error| ParseTeamError { position, error })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Team { ranges })
+    }
+}
+
+impl Team {
+    pub fn ranges(&self) -> &[SectionRange] {
+        &self.ranges
+    }
+
+    // Position of an assignment that contains every other assignment in the
+    // team. Any such assignment must start first and end last.
+    pub fn covering_assignment(&self) -> Option<usize> {
+        let start = self.ranges.iter().map(SectionRange::start).min()?;
+        let end = self.ranges.iter().map(SectionRange::end).max()?;
+        self.ranges
+            .iter()
+            .position(|r| r.start() == start && r.end() == end)
+    }
+
+    // True if any two assignments in the team share a section. Once sorted by
+    // start, an assignment overlaps an earlier one exactly when it starts
+    // before the furthest end seen so far.
+    pub fn has_overlap(&self) -> bool {
+        let mut sorted = self.ranges.clone();
+        sorted.sort();
+        let mut max_end = None::<u32>;
+        for range in sorted {
+            if max_end.is_some_and(|end| end >= range.start()) {
+                return true;
+            }
+            max_end = Some(max_end.map_or(range.end(), |end| end.max(range.end())));
+        }
+        false
+    }
+
+    // Every section that at least one member of the team is assigned to
+    pub fn coverage(&self) -> IntervalSet {
+        self.ranges.iter().copied().collect()
+    }
+}
@@ -0,0 +1,29 @@
+// Fixtures shared by the unit tests of each module
+
+use std::str::FromStr;
+
+use crate::{
+    section::SectionRange,
+    sweep::{Assignment, Elf},
+};
+
+pub fn range(s: &str) -> SectionRange {
+    SectionRange::from_str(s).unwrap()
+}
+
+// Comma-separated ranges, as on an input line
+pub fn ranges(s: &str) -> Vec<SectionRange> {
+    s.split(',').map(range).collect()
+}
+
+// One assignment per range, each from its own line
+pub fn assignments(ranges: &[&str]) -> Vec<Assignment> {
+    ranges
+        .iter()
+        .zip(1..)
+        .map(|(r, line)| Assignment {
+            elf: Elf { line, position: 0 },
+            range: range(r),
+        })
+        .collect()
+}
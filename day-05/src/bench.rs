@@ -0,0 +1,99 @@
+use std::time::Instant;
+
+use crate::{
+    crane::{self, CrateMover, CrateMover9000, CrateMover9001, OnInvalid},
+    drawing::Drawing,
+    first_instruction_line, parse_stack_data, trace, Crate, Move,
+};
+
+// Most crates a generated move will take at once
+const MAX_MOVE: usize = 1_000;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// Generate puzzle input with `crates` crates spread over `stacks` stacks and
+// `moves` instructions that are all legal for every crane model
+pub fn generate_input(stacks: usize, crates: usize, moves: usize, seed: u64) -> String {
+    let mut rng = XorShift(seed.max(1));
+    let mut columns = vec![Vec::<Crate>::new(); stacks];
+    for _ in 0..crates {
+        let label = char::from(b'A' + rng.below(26) as u8);
+        columns[rng.below(stacks)].push(label.to_string());
+    }
+
+    let mut heights = columns.iter().map(Vec::len).collect::<Vec<_>>();
+    let mut output = format!("{}\n\n", Drawing(&columns));
+    for _ in 0..moves {
+        let from = loop {
+            let from = rng.below(stacks);
+            if heights[from] > 0 {
+                break from;
+            }
+        };
+        let to = (from + 1 + rng.below(stacks - 1)) % stacks;
+        let n = 1 + rng.below(heights[from].min(MAX_MOVE));
+        heights[from] -= n;
+        heights[to] += n;
+        let op = Move {
+            n,
+            from: from + 1,
+            to: to + 1,
+        };
+        output.push_str(&format!("{op}\n"));
+    }
+    output
+}
+
+pub fn run(moves: usize) {
+    const SEED: u64 = 2022;
+    const STACKS: usize = 9;
+    const CRATES: usize = 20_000;
+    let input = generate_input(STACKS, CRATES, moves, SEED);
+    let (stack_data, instructions) = input
+        .split_once("\n\n")
+        .expect("Generated input has a drawing and instructions");
+    let stacks = parse_stack_data(stack_data).expect("Generated drawing is valid");
+    let first_line = first_instruction_line(stack_data);
+    println!("day-05/bench: {STACKS} stacks, {CRATES} crates, {moves} moves");
+
+    let movers: [&dyn CrateMover; 2] = [&CrateMover9000, &CrateMover9001];
+    for mover in movers {
+        let start = Instant::now();
+        let simulation =
+            crane::simulate_checked(mover, &stacks, instructions, first_line, OnInvalid::Stop)
+                .expect("Generated moves are legal");
+        let naive = crane::top_crates(&simulation.stacks);
+        let naive_time = start.elapsed();
+
+        let start = Instant::now();
+        let traced = trace::trace_tops(mover, &stacks, instructions, first_line, OnInvalid::Stop)
+            .expect("Both models can be traced")
+            .expect("Generated moves are legal")
+            .top_crates();
+        let traced_time = start.elapsed();
+
+        assert_eq!(naive, traced, "Both simulations agree");
+        println!("  {}: {naive}", mover.name());
+        println!("    simulated: {naive_time:?}");
+        println!("    traced:    {traced_time:?}");
+        println!(
+            "    speedup:   {:.1}x",
+            naive_time.as_secs_f64() / traced_time.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::{fmt, num::NonZeroUsize};
+
+use crate::{parse_move_op, Crate, Move};
+
+// One lift of a crane: the top `n` crates of the source stack, put down on
+// the target in the same order or reversed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lift {
+    pub n: usize,
+    pub reversed: bool,
+}
+
+// A model of crane. Each model moves crates from the top of one stack to the
+// top of another in its own way, described as a series of lifts so that the
+// same model can move crate labels, crate ids or anything else.
+pub trait CrateMover {
+    fn name(&self) -> String;
+
+    // Call `on_lift` for each lift that moves `n` crates off a source stack
+    // that is `height` crates tall, in the order they happen
+    fn lifts(&self, height: usize, n: usize, on_lift: &mut dyn FnMut(Lift));
+
+    // After moving `n` crates, the crate `depth` from the top of the target
+    // (0 is the top) came from this depth in the source. Models where that
+    // depends on anything but `n` and `depth` return `None` and can not be
+    // traced backwards.
+    fn source_depth(&self, _n: usize, _depth: usize) -> Option<usize> {
+        None
+    }
+}
+
+// Move `n` crates from the top of `source` to the top of `target`; the tops
+// of both stacks are at the end of their `Vec`s
+pub fn move_crates<T>(mover: &dyn CrateMover, source: &mut Vec<T>, target: &mut Vec<T>, n: usize) {
+    mover.lifts(source.len(), n, &mut |lift| {
+        let split_index = source.len() - lift.n;
+        let lifted = source.drain(split_index..);
+        if lift.reversed {
+            target.extend(lifted.rev());
+        } else {
+            target.extend(lifted);
+        }
+    });
+}
+
+// `source_depth` for a crane that lifts at most `capacity` crates at a time,
+// keeping the order of each lift. The last lift is the smallest and ends up on
+// top; every lift below it is full.
+fn batched_source_depth(capacity: usize, n: usize, depth: usize) -> usize {
+    let lifts = n.div_ceil(capacity);
+    let last_lift = n - capacity * (lifts - 1);
+    if depth < last_lift {
+        return capacity * (lifts - 1) + depth;
+    }
+    let below_last = depth - last_lift;
+    let lifts_from_top = below_last / capacity;
+    capacity * (lifts - 2 - lifts_from_top) + below_last % capacity
+}
+
+// Moves crates one at a time, so they land in reverse order
+pub struct CrateMover9000;
+
+impl CrateMover for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
+
+    // Moving crates one by one ends the same as one reversed lift
+    fn lifts(&self, _height: usize, n: usize, on_lift: &mut dyn FnMut(Lift)) {
+        on_lift(Lift { n, reversed: true });
+    }
+
+    fn source_depth(&self, n: usize, depth: usize) -> Option<usize> {
+        Some(n - 1 - depth)
+    }
+}
+
+// Moves all the crates at once, so they keep their order
+pub struct CrateMover9001;
+
+impl CrateMover for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn lifts(&self, _height: usize, n: usize, on_lift: &mut dyn FnMut(Lift)) {
+        on_lift(Lift { n, reversed: false });
+    }
+
+    fn source_depth(&self, _n: usize, depth: usize) -> Option<usize> {
+        Some(depth)
+    }
+}
+
+// Lifts at most `capacity` crates at a time. Each lift keeps the order of the
+// crates in it, so a capacity of 1 behaves like the 9000 and a capacity at
+// least as tall as any stack behaves like the 9001.
+pub struct CapacityLimited {
+    capacity: NonZeroUsize,
+}
+
+impl CapacityLimited {
+    // A crane has to lift at least one crate, so a capacity of 0 is `None`
+    pub fn new(capacity: usize) -> Option<CapacityLimited> {
+        NonZeroUsize::new(capacity).map(|capacity| CapacityLimited { capacity })
+    }
+}
+
+impl CrateMover for CapacityLimited {
+    fn name(&self) -> String {
+        format!("capacity-limited ({} per lift)", self.capacity)
+    }
+
+    fn lifts(&self, _height: usize, n: usize, on_lift: &mut dyn FnMut(Lift)) {
+        let mut remaining = n;
+        while remaining > 0 {
+            let batch = remaining.min(self.capacity.get());
+            on_lift(Lift {
+                n: batch,
+                reversed: false,
+            });
+            remaining -= batch;
+        }
+    }
+
+    fn source_depth(&self, n: usize, depth: usize) -> Option<usize> {
+        Some(batched_source_depth(self.capacity.get(), n, depth))
+    }
+}
+
+// Can only reach the top half of a stack (rounded up), so moving more crates
+// than that takes several lifts. Each lift keeps the order of its crates.
+pub struct TopHalf;
+
+impl CrateMover for TopHalf {
+    fn name(&self) -> String {
+        "top-half".to_owned()
+    }
+
+    fn lifts(&self, height: usize, n: usize, on_lift: &mut dyn FnMut(Lift)) {
+        let mut height = height;
+        let mut remaining = n;
+        while remaining > 0 {
+            let reach = height.div_ceil(2);
+            let batch = remaining.min(reach);
+            on_lift(Lift {
+                n: batch,
+                reversed: false,
+            });
+            height -= batch;
+            remaining -= batch;
+        }
+    }
+}
+
+// What to do when an instruction can not be carried out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnInvalid {
+    #[default]
+    Stop,
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IllegalMove {
+    Unparseable,
+    // Stacks are numbered from 1 up to the number of stacks
+    NoSuchStack { stack: usize },
+    NotEnoughCrates { stack: usize, needed: usize },
+}
+
+// An instruction that can not be carried out. `line` is the line of the input
+// the instruction was on and `stack_sizes` is the height of every stack just
+// before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveError {
+    pub line: usize,
+    pub instruction: String,
+    pub stack_sizes: Vec<usize>,
+    pub reason: IllegalMove,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} '{}': ", self.line, self.instruction)?;
+        match self.reason {
+            IllegalMove::Unparseable => write!(f, "expected 'move n from a to b'")?,
+            IllegalMove::NoSuchStack { stack } => write!(
+                f,
+                "there is no stack {stack}, stacks are 1 to {}",
+                self.stack_sizes.len()
+            )?,
+            IllegalMove::NotEnoughCrates { stack, needed } => write!(
+                f,
+                "can not take {needed} crates from stack {stack}, it only has {}",
+                self.stack_sizes[stack - 1]
+            )?,
+        }
+        let sizes = self
+            .stack_sizes
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>();
+        write!(f, " (stack sizes {})", sizes.join(" "))
+    }
+}
+
+// Check that `op` can be carried out on `stacks` as they are now
+pub fn validate<T>(op: &Move, stacks: &[Vec<T>]) -> Result<(), IllegalMove> {
+    validate_heights(op, stacks.len(), |i| stacks[i].len())
+}
+
+// As `validate`, for when only the height of each stack is known
+pub fn validate_heights(
+    op: &Move,
+    stack_count: usize,
+    height: impl Fn(usize) -> usize,
+) -> Result<(), IllegalMove> {
+    for stack in [op.from, op.to] {
+        if stack == 0 || stack > stack_count {
+            return Err(IllegalMove::NoSuchStack { stack });
+        }
+    }
+    if height(op.from - 1) < op.n {
+        return Err(IllegalMove::NotEnoughCrates {
+            stack: op.from,
+            needed: op.n,
+        });
+    }
+    Ok(())
+}
+
+// The final stacks, and every instruction that was skipped on the way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation<T = Crate> {
+    pub stacks: Vec<Vec<T>>,
+    pub skipped: Vec<MoveError>,
+}
+
+// Parse and run each instruction in turn, checking it against the stacks as
+// they are at that point. `first_line` is the line of the input that the
+// instructions start on; blank lines are ignored.
+pub fn simulate_checked(
+    mover: &dyn CrateMover,
+    stacks: &[Vec<Crate>],
+    instructions: &str,
+    first_line: usize,
+    on_invalid: OnInvalid,
+) -> Result<Simulation, MoveError> {
+    simulate_steps(
+        mover,
+        stacks,
+        instructions,
+        first_line,
+        on_invalid,
+        |_, _, _| {},
+    )
+}
+
+// As `simulate_checked`, for stacks of any kind of crate, calling `on_step`
+// with the line of each move that was carried out, the move, and the stacks
+// just after it
+pub fn simulate_steps<T: Clone>(
+    mover: &dyn CrateMover,
+    stacks: &[Vec<T>],
+    instructions: &str,
+    first_line: usize,
+    on_invalid: OnInvalid,
+    mut on_step: impl FnMut(usize, &Move, &[Vec<T>]),
+) -> Result<Simulation<T>, MoveError> {
+    let mut stacks = stacks.to_vec();
+    let mut skipped = Vec::new();
+    for (instruction, line) in instructions.lines().zip(first_line..) {
+        if instruction.trim().is_empty() {
+            continue;
+        }
+        let checked = parse_move_op(instruction)
+            .ok_or(IllegalMove::Unparseable)
+            .and_then(|op| validate(&op, &stacks).map(|_| op));
+        match checked {
+            Ok(op) => {
+                simulate_in_place(mover, &mut stacks, &op);
+                on_step(line, &op, &stacks);
+            }
+            Err(reason) => {
+                let error = MoveError {
+                    line,
+                    instruction: instruction.to_owned(),
+                    stack_sizes: stacks.iter().map(Vec::len).collect(),
+                    reason,
+                };
+                match on_invalid {
+                    OnInvalid::Stop => return Err(error),
+                    OnInvalid::Skip => skipped.push(error),
+                }
+            }
+        }
+    }
+    Ok(Simulation { stacks, skipped })
+}
+
+// Carry out a move that has already been validated
+pub fn simulate_in_place<T>(mover: &dyn CrateMover, stacks: &mut [Vec<T>], op: &Move) {
+    if op.from == op.to {
+        return;
+    }
+    let (source, target) = two_stacks(stacks, op.from - 1, op.to - 1);
+    move_crates(mover, source, target, op.n);
+}
+
+pub fn two_stacks<T>(stacks: &mut [Vec<T>], a: usize, b: usize) -> (&mut Vec<T>, &mut Vec<T>) {
+    assert_ne!(a, b, "Source and target must be different stacks");
+    if a < b {
+        let (left, right) = stacks.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = stacks.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+pub fn top_crates(stacks: &[Vec<Crate>]) -> String {
+    stacks
+        .iter()
+        .map(|s| s.last().map_or("?", String::as_str))
+        .collect()
+}
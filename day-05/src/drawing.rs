@@ -0,0 +1,357 @@
+use std::fmt;
+
+use crate::Crate;
+
+// `line` is the 1-based line of the drawing the problem is on and columns are
+// 1-based character positions on that line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDrawingError {
+    pub line: usize,
+    pub kind: DrawingErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DrawingErrorKind {
+    // There is no row of stack numbers
+    NoLabels,
+    // Stacks are numbered 1, 2, 3, ... from left to right
+    BadLabel {
+        column: usize,
+        expected: usize,
+        found: String,
+    },
+    UnclosedCrate {
+        column: usize,
+    },
+    EmptyCrate {
+        column: usize,
+    },
+    StrayText {
+        column: usize,
+        text: char,
+    },
+    // Two crates on the same row are closest to the same stack number
+    SharedStack {
+        column: usize,
+        stack: usize,
+    },
+    // A crate with nothing beneath it
+    FloatingCrate {
+        column: usize,
+        stack: usize,
+    },
+}
+
+impl fmt::Display for ParseDrawingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            DrawingErrorKind::NoLabels => write!(f, "drawing has no row of stack numbers"),
+            DrawingErrorKind::BadLabel {
+                column,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected stack {expected} at column {column}, found '{found}'"
+            ),
+            DrawingErrorKind::UnclosedCrate { column } => {
+                write!(f, "crate at column {column} has no closing ']'")
+            }
+            DrawingErrorKind::EmptyCrate { column } => {
+                write!(f, "crate at column {column} has no label")
+            }
+            DrawingErrorKind::StrayText { column, text } => {
+                write!(f, "unexpected '{text}' at column {column}")
+            }
+            DrawingErrorKind::SharedStack { column, stack } => write!(
+                f,
+                "crate at column {column} is a second crate on stack {stack}"
+            ),
+            DrawingErrorKind::FloatingCrate { column, stack } => write!(
+                f,
+                "crate at column {column} on stack {stack} has nothing beneath it"
+            ),
+        }
+    }
+}
+
+// Positions are doubled so that the middle of an even-width span is still a
+// whole column
+fn doubled_centre(start: usize, end: usize) -> usize {
+    start + end - 1
+}
+
+// Spans of non-blank characters on a line, as `(start, end)` columns
+fn words(line: &[char]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (column, c) in line.iter().enumerate() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(column),
+            (true, Some(s)) => {
+                spans.push((s, column));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, line.len()));
+    }
+    spans
+}
+
+// Parse the drawing above the instructions. Each stack's column comes from
+// where its number is on the bottom row, and every crate belongs to the stack
+// whose number is closest to the middle of the crate. Crate labels can be any
+// length, and lines need not be padded or evenly spaced.
+pub fn parse_stack_data(stack_data: &str) -> Result<Vec<Vec<Crate>>, ParseDrawingError> {
+    let lines = stack_data
+        .lines()
+        .map(|line| line.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let label_row = lines
+        .iter()
+        .rposition(|line| line.iter().any(|c| !c.is_whitespace()))
+        .ok_or(ParseDrawingError {
+            line: 1,
+            kind: DrawingErrorKind::NoLabels,
+        })?;
+
+    let mut label_centres = Vec::new();
+    for (expected, (start, end)) in (1..).zip(words(&lines[label_row])) {
+        let found = lines[label_row][start..end].iter().collect::<String>();
+        if found.parse::<usize>() != Ok(expected) {
+            return Err(ParseDrawingError {
+                line: label_row + 1,
+                kind: DrawingErrorKind::BadLabel {
+                    column: start + 1,
+                    expected,
+                    found,
+                },
+            });
+        }
+        label_centres.push(doubled_centre(start, end));
+    }
+
+    let mut stacks = vec![Vec::new(); label_centres.len()];
+    for (row, line) in lines[..label_row].iter().enumerate().rev() {
+        let error = |kind| ParseDrawingError {
+            line: row + 1,
+            kind,
+        };
+        // Number of crates below this row in every stack it has a crate on
+        let level = label_row - 1 - row;
+        let mut on_this_row = vec![false; stacks.len()];
+        let mut column = 0;
+        while column < line.len() {
+            let c = line[column];
+            if c.is_whitespace() {
+                column += 1;
+                continue;
+            }
+            if c != '[' {
+                return Err(error(DrawingErrorKind::StrayText {
+                    column: column + 1,
+                    text: c,
+                }));
+            }
+            let close = line[column + 1..]
+                .iter()
+                .position(|&c| c == ']' || c == '[')
+                .map(|offset| column + 1 + offset)
+                .filter(|&close| line[close] == ']')
+                .ok_or(error(DrawingErrorKind::UnclosedCrate {
+                    column: column + 1,
+                }))?;
+            let label = line[column + 1..close].iter().collect::<String>();
+            if label.trim().is_empty() {
+                return Err(error(DrawingErrorKind::EmptyCrate { column: column + 1 }));
+            }
+
+            let centre = doubled_centre(column, close + 1);
+            let index = (0..label_centres.len())
+                .min_by_key(|&i| label_centres[i].abs_diff(centre))
+                .expect("The label row has at least one stack number");
+            if on_this_row[index] {
+                return Err(error(DrawingErrorKind::SharedStack {
+                    column: column + 1,
+                    stack: index + 1,
+                }));
+            }
+            if stacks[index].len() != level {
+                return Err(error(DrawingErrorKind::FloatingCrate {
+                    column: column + 1,
+                    stack: index + 1,
+                }));
+            }
+            on_this_row[index] = true;
+            stacks[index].push(label);
+            column = close + 1;
+        }
+    }
+    Ok(stacks)
+}
+
+// Draws stacks the way the puzzle input does: one row per level with the
+// tallest stack's top crate first, every row padded to the full width, and
+// the stack numbers underneath. Each column is as wide as its widest crate.
+// Parsing the drawing gives back the stacks.
+pub struct Drawing<'a>(pub &'a [Vec<Crate>]);
+
+impl fmt::Display for Drawing<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stacks = self.0;
+        let widths = (1..)
+            .zip(stacks)
+            .map(|(n, stack): (usize, _)| {
+                let widest = stack.iter().map(|c| c.chars().count()).max().unwrap_or(1);
+                (widest + 2).max(n.to_string().len())
+            })
+            .collect::<Vec<_>>();
+        let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let cells = stacks
+                .iter()
+                .zip(&widths)
+                .map(|(stack, &width)| match stack.get(level) {
+                    Some(label) => format!("{:<width$}", format!("[{label}]")),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        let labels = (1..)
+            .zip(&widths)
+            .map(|(n, &width): (usize, _)| format!("{n:^width$}"))
+            .collect::<Vec<_>>();
+        write!(f, "{}", labels.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod drawing_tests {
+    use super::*;
+
+    fn stacks(columns: &[&[&str]]) -> Vec<Vec<Crate>> {
+        columns
+            .iter()
+            .map(|column| column.iter().map(|&c| c.to_owned()).collect())
+            .collect()
+    }
+
+    fn kind(drawing: &str) -> DrawingErrorKind {
+        parse_stack_data(drawing).unwrap_err().kind
+    }
+
+    fn round_trip(drawing: &str) {
+        let stacks = parse_stack_data(drawing).unwrap();
+        let rendered = Drawing(&stacks).to_string();
+        assert_eq!(rendered, drawing);
+        assert_eq!(parse_stack_data(&rendered).unwrap(), stacks);
+    }
+
+    #[test]
+    fn the_sample_drawing_round_trips() {
+        let (stack_data, _) = include_str!("../sample_input.txt")
+            .split_once("\n\n")
+            .unwrap();
+        round_trip(stack_data);
+    }
+
+    #[test]
+    fn empty_stacks_keep_their_column() {
+        let stacks = stacks(&[&["A"], &[], &["B", "C"]]);
+        let rendered = Drawing(&stacks).to_string();
+        assert_eq!(rendered, "        [C]\n[A]     [B]\n 1   2   3 ");
+        assert_eq!(parse_stack_data(&rendered).unwrap(), stacks);
+    }
+
+    #[test]
+    fn stacks_with_no_crates_are_just_labels() {
+        let stacks = stacks(&[&[], &[]]);
+        let rendered = Drawing(&stacks).to_string();
+        assert_eq!(rendered, " 1   2 ");
+        assert_eq!(parse_stack_data(&rendered).unwrap(), stacks);
+    }
+
+    #[test]
+    fn trailing_whitespace_is_optional() {
+        let drawing = "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3";
+        assert_eq!(
+            parse_stack_data(drawing).unwrap(),
+            stacks(&[&["Z", "N"], &["M", "C", "D"], &["P"]])
+        );
+    }
+
+    #[test]
+    fn crate_labels_can_be_wider_than_one_character() {
+        let drawing = "[AB]\n[CD] [E]  [FGH]\n 1    2    3";
+        let expected = stacks(&[&["CD", "AB"], &["E"], &["FGH"]]);
+        assert_eq!(parse_stack_data(drawing).unwrap(), expected);
+        let rendered = Drawing(&expected).to_string();
+        assert_eq!(rendered, "[AB]          \n[CD] [E] [FGH]\n 1    2    3  ");
+        assert_eq!(parse_stack_data(&rendered).unwrap(), expected);
+    }
+
+    #[test]
+    fn more_than_nine_stacks() {
+        let columns = (b'A'..=b'L')
+            .map(|label| vec![char::from(label).to_string()])
+            .collect::<Vec<_>>();
+        let rendered = Drawing(&columns).to_string();
+        assert!(rendered.ends_with(" 9  10  11  12 "));
+        assert_eq!(parse_stack_data(&rendered).unwrap(), columns);
+    }
+
+    #[test]
+    fn irregular_spacing_follows_the_labels() {
+        let drawing = "  [X]\n[Y]  [Z]     [W]\n 1    2       3";
+        assert_eq!(
+            parse_stack_data(drawing).unwrap(),
+            stacks(&[&["Y", "X"], &["Z"], &["W"]])
+        );
+    }
+
+    #[test]
+    fn malformed_drawings_are_reported_with_their_position() {
+        assert_eq!(kind(""), DrawingErrorKind::NoLabels);
+        assert_eq!(
+            kind("[A]\n 1   3"),
+            DrawingErrorKind::BadLabel {
+                column: 6,
+                expected: 2,
+                found: "3".to_owned()
+            }
+        );
+        assert_eq!(
+            kind("[A\n 1"),
+            DrawingErrorKind::UnclosedCrate { column: 1 }
+        );
+        assert_eq!(kind("[]\n 1"), DrawingErrorKind::EmptyCrate { column: 1 });
+        assert_eq!(
+            kind("[A] x\n 1   2"),
+            DrawingErrorKind::StrayText {
+                column: 5,
+                text: 'x'
+            }
+        );
+        assert_eq!(
+            kind("[A][B]\n 1           2"),
+            DrawingErrorKind::SharedStack {
+                column: 4,
+                stack: 1
+            }
+        );
+        let error = parse_stack_data("    [A]\n[B]\n 1   2").unwrap_err();
+        assert_eq!(error.line, 1);
+        assert_eq!(
+            error.kind,
+            DrawingErrorKind::FloatingCrate {
+                column: 5,
+                stack: 2
+            }
+        );
+    }
+}
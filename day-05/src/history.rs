@@ -0,0 +1,339 @@
+use crate::{
+    crane::{self, CrateMover, MoveError, OnInvalid},
+    Crate, Move,
+};
+
+// Crates are numbered in the order they appear in the drawing, stack by
+// stack from the bottom up, so two crates with the same label can still be
+// told apart
+pub type CrateId = usize;
+
+// A move that was carried out and the line of the input it came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub line: usize,
+    pub op: Move,
+}
+
+// The stacks as crate ids, and for every crate the move that last touched
+// it, just after some number of moves
+#[derive(Debug, Clone)]
+struct Checkpoint {
+    stacks: Vec<Vec<CrateId>>,
+    last_touch: Vec<Option<usize>>,
+}
+
+// A checkpoint interval for `moves` moves of `crates` crates. Checkpoints
+// take `crates` slots each, so keeping one every sqrt(moves * crates) moves
+// balances their memory against the moves replayed to reach any state: both
+// grow as sqrt(moves * crates).
+pub fn checkpoint_interval(moves: usize, crates: usize) -> usize {
+    (moves.saturating_mul(crates) as f64).sqrt().max(1.0) as usize
+}
+
+// Every move of a simulation, with a full copy of the stacks kept every
+// `interval` moves. Moves are counted from 1 and move 0 is the drawing, so
+// the state after any move is at most `interval - 1` moves from a checkpoint.
+//
+// Crane models only ever move crates around, so the history runs the crane
+// on each crate's id and looks up the real labels when asked.
+pub struct History<'a> {
+    mover: &'a dyn CrateMover,
+    labels: Vec<Crate>,
+    steps: Vec<Step>,
+    checkpoints: Vec<Checkpoint>,
+    interval: usize,
+    pub skipped: Vec<MoveError>,
+}
+
+impl<'a> History<'a> {
+    pub fn record(
+        mover: &'a dyn CrateMover,
+        stacks: &[Vec<Crate>],
+        instructions: &str,
+        first_line: usize,
+        on_invalid: OnInvalid,
+        interval: usize,
+    ) -> Result<History<'a>, MoveError> {
+        assert!(interval > 0, "Checkpoints need a positive interval");
+        let labels = stacks.iter().flatten().cloned().collect::<Vec<_>>();
+        let mut next_id = 0..;
+        let ids = stacks
+            .iter()
+            .map(|stack| stack.iter().zip(&mut next_id).map(|(_, id)| id).collect())
+            .collect::<Vec<Vec<_>>>();
+
+        let mut last_touch = vec![None; labels.len()];
+        let mut checkpoints = vec![Checkpoint {
+            stacks: ids.clone(),
+            last_touch: last_touch.clone(),
+        }];
+        let mut steps = Vec::new();
+        let simulation = crane::simulate_steps(
+            mover,
+            &ids,
+            instructions,
+            first_line,
+            on_invalid,
+            |line, op, stacks| {
+                steps.push(Step { line, op: *op });
+                touch(&mut last_touch, op, stacks, steps.len());
+                if steps.len() % interval == 0 {
+                    checkpoints.push(Checkpoint {
+                        stacks: stacks.to_vec(),
+                        last_touch: last_touch.clone(),
+                    });
+                }
+            },
+        )?;
+        Ok(History {
+            mover,
+            labels,
+            steps,
+            checkpoints,
+            interval,
+            skipped: simulation.skipped,
+        })
+    }
+
+    // Number of moves that were carried out
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    // Move `k`, counting from 1
+    pub fn step(&self, k: usize) -> Option<&Step> {
+        k.checked_sub(1).and_then(|i| self.steps.get(i))
+    }
+
+    pub fn label(&self, id: CrateId) -> &str {
+        &self.labels[id]
+    }
+
+    // Every crate with the given label
+    pub fn crates_labelled(&self, label: &str) -> Vec<CrateId> {
+        (0..self.labels.len())
+            .filter(|&id| self.labels[id] == label)
+            .collect()
+    }
+
+    // Replay from the last checkpoint at or before move `k`
+    fn replay(&self, k: usize) -> Checkpoint {
+        assert!(k <= self.len(), "There are only {} moves", self.len());
+        let mut state = self.checkpoints[k / self.interval].clone();
+        for done in (k / self.interval) * self.interval..k {
+            let op = &self.steps[done].op;
+            crane::simulate_in_place(self.mover, &mut state.stacks, op);
+            touch(&mut state.last_touch, op, &state.stacks, done + 1);
+        }
+        state
+    }
+
+    // The stacks just after move `k`, as crate ids
+    pub fn ids_after(&self, k: usize) -> Vec<Vec<CrateId>> {
+        self.replay(k).stacks
+    }
+
+    // The stacks just after move `k`
+    pub fn state_after(&self, k: usize) -> Vec<Vec<Crate>> {
+        self.ids_after(k)
+            .iter()
+            .map(|stack| stack.iter().map(|&id| self.labels[id].clone()).collect())
+            .collect()
+    }
+
+    pub fn top_crates_after(&self, k: usize) -> String {
+        crane::top_crates(&self.state_after(k))
+    }
+
+    // The last of the first `k` moves to pick up the crate, if any did
+    pub fn last_touched(&self, id: CrateId, k: usize) -> Option<usize> {
+        self.replay(k).last_touch[id]
+    }
+}
+
+// Record that move `k` put the top `op.n` crates of its target stack there
+fn touch(last_touch: &mut [Option<usize>], op: &Move, stacks: &[Vec<CrateId>], k: usize) {
+    if op.from == op.to {
+        return;
+    }
+    let target = &stacks[op.to - 1];
+    for &id in &target[target.len() - op.n..] {
+        last_touch[id] = Some(k);
+    }
+}
+
+// Where the user is in a history, with positions they can undo back to and
+// redo forward to
+#[derive(Debug, Default)]
+pub struct Cursor {
+    pub position: usize,
+    undo: Vec<usize>,
+    redo: Vec<usize>,
+}
+
+impl Cursor {
+    // Move to `position`, which can be undone
+    pub fn go_to(&mut self, position: usize) {
+        if position != self.position {
+            self.undo.push(self.position);
+            self.redo.clear();
+            self.position = position;
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(previous) => {
+                self.redo.push(self.position);
+                self.position = previous;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(next) => {
+                self.undo.push(self.position);
+                self.position = next;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::*;
+    use crate::{
+        bench::generate_input,
+        crane::{simulate_checked, CrateMover9000, CrateMover9001, TopHalf},
+        first_instruction_line, parse_stack_data,
+    };
+
+    fn sample_history(mover: &dyn CrateMover, interval: usize) -> History<'_> {
+        let (stack_data, instructions) = include_str!("../sample_input.txt")
+            .split_once("\n\n")
+            .unwrap();
+        let stacks = parse_stack_data(stack_data).unwrap();
+        let first_line = first_instruction_line(stack_data);
+        History::record(
+            mover,
+            &stacks,
+            instructions,
+            first_line,
+            OnInvalid::Stop,
+            interval,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn tops_after_each_sample_move() {
+        let history = sample_history(&CrateMover9000, 2);
+        let tops = (0..=history.len())
+            .map(|k| history.top_crates_after(k))
+            .collect::<Vec<_>>();
+        assert_eq!(tops, vec!["NDP", "DCP", "?CZ", "M?Z", "CMZ"]);
+        assert_eq!(history.step(2).unwrap().line, 7);
+        assert!(history.step(0).is_none());
+    }
+
+    #[test]
+    fn every_state_matches_a_fresh_simulation() {
+        let input = generate_input(5, 40, 300, 7);
+        let (stack_data, instructions) = input.split_once("\n\n").unwrap();
+        let stacks = parse_stack_data(stack_data).unwrap();
+        let first_line = first_instruction_line(stack_data);
+        for mover in [
+            &CrateMover9000 as &dyn CrateMover,
+            &CrateMover9001,
+            &TopHalf,
+        ] {
+            let history = History::record(
+                mover,
+                &stacks,
+                instructions,
+                first_line,
+                OnInvalid::Stop,
+                16,
+            )
+            .unwrap();
+            let prefix_end = instructions.lines().take(150).map(|l| l.len() + 1).sum();
+            let expected = simulate_checked(
+                mover,
+                &stacks,
+                &instructions[..prefix_end],
+                first_line,
+                OnInvalid::Stop,
+            )
+            .unwrap();
+            assert_eq!(history.state_after(150), expected.stacks);
+        }
+    }
+
+    #[test]
+    fn last_touched_follows_one_crate() {
+        // Crate 1 is N and crate 4 is D, which moves 1 and 2 both pick up
+        let history = sample_history(&CrateMover9000, 3);
+        assert_eq!(history.label(4), "D");
+        assert_eq!(history.last_touched(4, 0), None);
+        assert_eq!(history.last_touched(4, 1), Some(1));
+        assert_eq!(history.last_touched(4, 4), Some(2));
+        assert_eq!(history.last_touched(1, 4), Some(2));
+        // P never leaves stack 3
+        assert_eq!(history.crates_labelled("P"), vec![5]);
+        assert_eq!(history.last_touched(5, 4), None);
+    }
+
+    #[test]
+    fn ids_tell_apart_crates_with_the_same_label() {
+        let stacks = parse_stack_data("[A] [A]\n 1   2").unwrap();
+        let history = History::record(
+            &CrateMover9001,
+            &stacks,
+            "move 1 from 2 to 1",
+            3,
+            OnInvalid::Stop,
+            1,
+        )
+        .unwrap();
+        assert_eq!(history.crates_labelled("A"), vec![0, 1]);
+        assert_eq!(history.ids_after(1), vec![vec![0, 1], vec![]]);
+        assert_eq!(history.last_touched(0, 1), None);
+        assert_eq!(history.last_touched(1, 1), Some(1));
+    }
+
+    #[test]
+    fn checkpoints_grow_slower_than_the_input() {
+        assert_eq!(checkpoint_interval(0, 0), 1);
+        assert_eq!(checkpoint_interval(4, 9), 6);
+        // A million moves of ten thousand crates keeps 10 checkpoints of
+        // 10,000 crates each, not 15,625 as with a fixed interval of 64
+        let interval = checkpoint_interval(1_000_000, 10_000);
+        assert_eq!(interval, 100_000);
+        assert_eq!(1_000_000 / interval, 10);
+    }
+
+    #[test]
+    fn undo_and_redo_retrace_the_cursor() {
+        let mut cursor = Cursor::default();
+        cursor.go_to(1);
+        cursor.go_to(4);
+        assert!(cursor.undo());
+        assert_eq!(cursor.position, 1);
+        assert!(cursor.redo());
+        assert_eq!(cursor.position, 4);
+        assert!(!cursor.redo());
+        assert!(cursor.undo());
+        cursor.go_to(2);
+        assert!(!cursor.redo());
+        assert!(cursor.undo());
+        assert!(cursor.undo());
+        assert_eq!(cursor.position, 0);
+        assert!(!cursor.undo());
+    }
+}
@@ -1,32 +1,39 @@
-use std::{fs, io};
-
-fn parse_stack_data(stack_data: &str) -> Option<Vec<Vec<char>>> {
-    const PACKAGE_WIDTH: usize = 3;
-    const PAD_WIDTH: usize = 1;
-
-    let mut stack_iter = stack_data.lines().rev();
-    let stack_labels = stack_iter.next()?;
-
-    let (_, last_label) = stack_labels.trim_end().rsplit_once(char::is_whitespace)?;
-    let stack_count = last_label.parse::<usize>().ok()?;
-    let mut stacks = vec![Vec::new(); stack_count];
-    for line in stack_iter {
-        let mut remaining = line;
-        for stack in stacks.iter_mut() {
-            let (package, rest) = remaining.split_at(PACKAGE_WIDTH);
-            let package_label = package.chars().nth(1)?;
-            if package_label != ' ' {
-                stack.push(package_label);
-            }
-            if !rest.is_empty() {
-                remaining = &rest[PAD_WIDTH..];
-            }
-        }
+use std::{
+    env, fmt, fs,
+    io::{self, BufRead},
+};
+
+mod bench;
+mod crane;
+mod drawing;
+mod history;
+mod planner;
+mod trace;
+
+use crane::{
+    CapacityLimited, CrateMover, CrateMover9000, CrateMover9001, MoveError, OnInvalid, TopHalf,
+};
+use drawing::{parse_stack_data, Drawing, ParseDrawingError};
+use history::{Cursor, History};
+
+// A crate's label, as drawn between its brackets
+type Crate = String;
+
+// `move n from a to b`, with stacks numbered from 1 as in the drawing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Move {
+    n: usize,
+    from: usize,
+    to: usize,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.n, self.from, self.to)
     }
-    Some(stacks)
 }
 
-fn parse_move_op(input: &str) -> Option<(usize, usize, usize)> {
+fn parse_move_op(input: &str) -> Option<Move> {
     let tokens = input.split_whitespace();
     // Skip 'move'
     let mut tokens = tokens.skip(1);
@@ -37,43 +44,474 @@ fn parse_move_op(input: &str) -> Option<(usize, usize, usize)> {
     // Skip 'to'
     let mut tokens = tokens.skip(1);
     let to = tokens.next().and_then(|s| s.parse::<usize>().ok())?;
-    Some((n, from, to))
+    Some(Move { n, from, to })
+}
+
+// Anything in the input that stops the simulation
+#[derive(Debug)]
+enum InputError {
+    Drawing(ParseDrawingError),
+    Move(MoveError),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Drawing(e) => write!(f, "invalid drawing: {e}"),
+            InputError::Move(e) => write!(f, "invalid move: {e}"),
+        }
+    }
+}
+
+impl From<ParseDrawingError> for InputError {
+    fn from(e: ParseDrawingError) -> Self {
+        InputError::Drawing(e)
+    }
+}
+
+impl From<MoveError> for InputError {
+    fn from(e: MoveError) -> Self {
+        InputError::Move(e)
+    }
+}
+
+// How to run the simulation, set from the command line
+#[derive(Debug, Clone, Copy, Default)]
+struct Options {
+    on_invalid: OnInvalid,
+    // Trace the top crates back through the moves instead of moving crates,
+    // for the crane models that allow it
+    fast: bool,
+}
+
+fn report_skipped(skipped: &[MoveError]) {
+    for error in skipped {
+        eprintln!("day-05;skipped {error}");
+    }
+}
+
+// Instructions start after the drawing and the blank line below it
+fn first_instruction_line(stack_data: &str) -> usize {
+    stack_data.lines().count() + 2
+}
+
+fn run(
+    mover: &dyn CrateMover,
+    stack_data: &str,
+    instructions: &str,
+    options: Options,
+) -> Result<String, InputError> {
+    let stacks = parse_stack_data(stack_data)?;
+    let first_line = first_instruction_line(stack_data);
+    if options.fast {
+        let traced =
+            trace::trace_tops(mover, &stacks, instructions, first_line, options.on_invalid);
+        if let Some(traced) = traced {
+            let tops = traced?;
+            report_skipped(&tops.skipped);
+            return Ok(tops.top_crates());
+        }
+    }
+    let simulation =
+        crane::simulate_checked(mover, &stacks, instructions, first_line, options.on_invalid)?;
+    report_skipped(&simulation.skipped);
+    Ok(crane::top_crates(&simulation.stacks))
+}
+
+fn part_1(stack_data: &str, instructions: &str, options: Options) -> Result<String, InputError> {
+    run(&CrateMover9000, stack_data, instructions, options)
+}
+
+fn part_2(stack_data: &str, instructions: &str, options: Options) -> Result<String, InputError> {
+    run(&CrateMover9001, stack_data, instructions, options)
+}
+
+fn print_position(history: &History, k: usize) {
+    match history.step(k) {
+        Some(step) => println!("after move {k} (line {}: {})", step.line, step.op),
+        None => println!("before the first move"),
+    }
+    println!("{}\n", Drawing(&history.state_after(k)));
+}
+
+// Step through the moves, reading commands from stdin:
+//   next (or an empty line), back, goto <k>  move through the history
+//   undo, redo                                retrace those movements
+//   tops <k>                                  top crates after move k
+//   touched <label>                           last move to pick up each such crate
+//   quit
+fn run_steps(
+    mover: &dyn CrateMover,
+    stack_data: &str,
+    instructions: &str,
+    on_invalid: OnInvalid,
+) -> Result<(), InputError> {
+    let stacks = parse_stack_data(stack_data)?;
+    let first_line = first_instruction_line(stack_data);
+    let interval = history::checkpoint_interval(
+        instructions.lines().count(),
+        stacks.iter().map(Vec::len).sum(),
+    );
+    let history = History::record(
+        mover,
+        &stacks,
+        instructions,
+        first_line,
+        on_invalid,
+        interval,
+    )?;
+    report_skipped(&history.skipped);
+
+    let mut cursor = Cursor::default();
+    print_position(&history, cursor.position);
+    for command in io::stdin().lock().lines() {
+        let command = command.expect("Commands are readable");
+        let mut words = command.split_whitespace();
+        let moved = match (words.next(), words.next().map(str::parse::<usize>)) {
+            (None | Some("next"), _) if cursor.position < history.len() => {
+                cursor.go_to(cursor.position + 1);
+                true
+            }
+            (Some("back"), _) if cursor.position > 0 => {
+                cursor.go_to(cursor.position - 1);
+                true
+            }
+            (Some("goto"), Some(Ok(k))) if k <= history.len() => {
+                cursor.go_to(k);
+                true
+            }
+            (Some("undo"), _) => cursor.undo(),
+            (Some("redo"), _) => cursor.redo(),
+            (Some("tops"), Some(Ok(k))) if k <= history.len() => {
+                println!("top crates after move {k}: {}", history.top_crates_after(k));
+                continue;
+            }
+            (Some("touched"), _) => {
+                let label = command.split_whitespace().nth(1).unwrap_or_default();
+                for id in history.crates_labelled(label) {
+                    match history.last_touched(id, cursor.position) {
+                        Some(k) => println!("crate {id} [{}]: move {k}", history.label(id)),
+                        None => println!("crate {id} [{}]: not moved yet", history.label(id)),
+                    }
+                }
+                continue;
+            }
+            (Some("quit"), _) => break,
+            _ => {
+                println!("can not '{command}' at move {}", cursor.position);
+                continue;
+            }
+        };
+        if moved {
+            print_position(&history, cursor.position);
+        } else {
+            println!("nothing to {command}");
+        }
+    }
+    println!(
+        "day-05;{} = {}",
+        mover.name(),
+        history.top_crates_after(history.len())
+    );
+    Ok(())
 }
 
-fn part_1(stack_data: &str, instructions: &str) -> String {
-    let mut stacks = parse_stack_data(stack_data).expect("Stack data is parseable");
-    for op in instructions.lines() {
-        let (n, from, to) = parse_move_op(op).expect("All moves in file are valid");
-        let split_index = stacks[from - 1].len() - n;
-        let last_n = stacks[from - 1].split_off(split_index);
-        stacks[to - 1].extend(last_n.iter().rev());
+// The top crates left by every crane model, including ones the puzzle
+// doesn't ask about
+fn run_models(stack_data: &str, instructions: &str, options: Options) -> Result<(), InputError> {
+    let capacity_limited = CapacityLimited::new(3).expect("3 crates is a valid capacity");
+    let models: [&dyn CrateMover; 4] = [
+        &CrateMover9000,
+        &CrateMover9001,
+        &capacity_limited,
+        &TopHalf,
+    ];
+    for mover in models {
+        let result = run(mover, stack_data, instructions, options)?;
+        println!("day-05;{} = {}", mover.name(), result);
     }
+    Ok(())
+}
 
-    stacks.iter().map(|s| s.last().unwrap_or(&'?')).collect()
+fn invalid_data(error: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
 }
 
-fn part_2(stack_data: &str, instructions: &str) -> String {
-    let mut stacks = parse_stack_data(stack_data).expect("Stack data is parseable");
-    for op in instructions.lines() {
-        let (n, from, to) = parse_move_op(op).expect("All moves in file are valid");
-        let split_index = stacks[from - 1].len() - n;
-        let mut last_n = stacks[from - 1].split_off(split_index);
-        stacks[to - 1].append(&mut last_n);
+// Print a shortest list of moves from the input's drawing to the drawing at
+// the top of `goal_path`, as instructions the simulator can read
+fn run_plan(mover: &dyn CrateMover, stack_data: &str, goal_path: &str) -> io::Result<()> {
+    const MAX_STATES: usize = 5_000_000;
+    let goal_input = fs::read_to_string(goal_path)?;
+    let goal_data = goal_input.split("\n\n").next().unwrap_or_default();
+    let start = parse_stack_data(stack_data).map_err(invalid_data)?;
+    let goal = parse_stack_data(goal_data).map_err(invalid_data)?;
+    let moves = planner::plan(mover, &start, &goal, MAX_STATES).map_err(invalid_data)?;
+    for op in &moves {
+        println!("{op}");
     }
-    stacks.iter().map(|s| s.last().unwrap_or(&'?')).collect()
+    eprintln!("day-05;{} plan = {} moves", mover.name(), moves.len());
+    Ok(())
 }
 
 fn main() -> io::Result<()> {
     const FILE_PATH: &str = "aoc_2022_day05_large_input.txt";
-    let input = fs::read_to_string(FILE_PATH)?;
 
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("bench") {
+        const DEFAULT_BENCH_MOVES: usize = 1_000_000;
+        let moves = args
+            .get(1)
+            .map(|s| s.parse::<usize>().expect("Move count is a valid usize"))
+            .unwrap_or(DEFAULT_BENCH_MOVES);
+        bench::run(moves);
+        return Ok(());
+    }
+
+    let input = fs::read_to_string(FILE_PATH)?;
     let (stack_data, instructions) = input
         .split_once("\n\n")
         .expect("Input has a stack data and instructions");
-    let result_1 = part_1(stack_data, instructions);
+
+    // By default the first illegal instruction stops the simulation
+    let on_invalid = if args.iter().any(|a| a == "--skip-invalid") {
+        OnInvalid::Skip
+    } else {
+        OnInvalid::Stop
+    };
+    let options = Options {
+        on_invalid,
+        fast: args.iter().any(|a| a == "--fast"),
+    };
+
+    let mover_arg = |i: usize| -> &dyn CrateMover {
+        match args.get(i).map(String::as_str) {
+            Some("part-2") => &CrateMover9001,
+            _ => &CrateMover9000,
+        }
+    };
+    match args.first().map(String::as_str) {
+        Some("step") => {
+            return run_steps(mover_arg(1), stack_data, instructions, on_invalid)
+                .map_err(invalid_data)
+        }
+        Some("models") => {
+            return run_models(stack_data, instructions, options).map_err(invalid_data)
+        }
+        Some("plan") => {
+            let goal_path = args
+                .get(1)
+                .expect("plan needs a file with the goal drawing");
+            return run_plan(mover_arg(2), stack_data, goal_path);
+        }
+        _ => {}
+    }
+
+    let result_1 = part_1(stack_data, instructions, options).map_err(invalid_data)?;
     println!("day-05;part-1 = {}", result_1);
 
-    let result_2 = part_2(stack_data, instructions);
+    let result_2 = part_2(stack_data, instructions, options).map_err(invalid_data)?;
     println!("day-05;part-2 = {}", result_2);
     Ok(())
 }
+
+#[cfg(test)]
+mod day_05_tests {
+    use crate::{
+        crane::{
+            move_crates, simulate_checked, top_crates, CapacityLimited, CrateMover9000,
+            IllegalMove, OnInvalid, TopHalf,
+        },
+        first_instruction_line, parse_move_op, parse_stack_data, part_1, part_2, run, Crate, Move,
+        Options,
+    };
+
+    const SAMPLE: &str = include_str!("../sample_input.txt");
+
+    fn sample() -> (&'static str, &'static str) {
+        SAMPLE.split_once("\n\n").unwrap()
+    }
+
+    fn crates(labels: &str) -> Vec<Crate> {
+        labels.chars().map(String::from).collect()
+    }
+
+    #[test]
+    fn sample_part_1() {
+        let (stack_data, instructions) = sample();
+        assert_eq!(
+            part_1(stack_data, instructions, Options::default()).unwrap(),
+            "CMZ"
+        );
+    }
+
+    #[test]
+    fn sample_part_2() {
+        let (stack_data, instructions) = sample();
+        assert_eq!(
+            part_2(stack_data, instructions, Options::default()).unwrap(),
+            "MCD"
+        );
+    }
+
+    #[test]
+    fn capacity_limited_crane_spans_both_models() {
+        let (stack_data, instructions) = sample();
+        assert_eq!(
+            run(
+                &CapacityLimited::new(1).unwrap(),
+                stack_data,
+                instructions,
+                Options::default()
+            )
+            .unwrap(),
+            "CMZ"
+        );
+        assert_eq!(
+            run(
+                &CapacityLimited::new(100).unwrap(),
+                stack_data,
+                instructions,
+                Options::default()
+            )
+            .unwrap(),
+            "MCD"
+        );
+    }
+
+    #[test]
+    fn a_crane_has_to_lift_something() {
+        assert!(CapacityLimited::new(0).is_none());
+    }
+
+    #[test]
+    fn capacity_limited_crane_lifts_in_batches() {
+        let mut source = crates("ABCDE");
+        let mut target = vec![];
+        let crane = CapacityLimited::new(2).unwrap();
+        move_crates(&crane, &mut source, &mut target, 5);
+        assert!(source.is_empty());
+        assert_eq!(target, crates("DEBCA"));
+    }
+
+    #[test]
+    fn top_half_crane_reaches_half_the_stack_per_lift() {
+        let mut source = crates("ABCDEF");
+        let mut target = crates("X");
+        // First lift reaches D-F, then B-C, then A
+        move_crates(&TopHalf, &mut source, &mut target, 6);
+        assert_eq!(target, crates("XDEFBCA"));
+    }
+
+    #[test]
+    fn moves_onto_the_same_stack_change_nothing() {
+        assert_eq!(
+            checked("move 2 from 1 to 1", OnInvalid::Stop),
+            Ok("NDP".to_owned())
+        );
+    }
+
+    #[test]
+    fn moves_display_as_instructions() {
+        let line = "move 12 from 3 to 10";
+        assert_eq!(parse_move_op(line).unwrap().to_string(), line);
+    }
+
+    #[test]
+    fn parse_move_op_reads_every_line() {
+        let (_, instructions) = sample();
+        let moves = instructions
+            .lines()
+            .map(|line| parse_move_op(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(moves.len(), 4);
+        assert_eq!(
+            moves[0],
+            Move {
+                n: 1,
+                from: 2,
+                to: 1
+            }
+        );
+    }
+
+    fn checked(instructions: &str, on_invalid: OnInvalid) -> Result<String, IllegalMove> {
+        let (stack_data, _) = sample();
+        let stacks = parse_stack_data(stack_data).unwrap();
+        let first_line = first_instruction_line(stack_data);
+        simulate_checked(
+            &CrateMover9000,
+            &stacks,
+            instructions,
+            first_line,
+            on_invalid,
+        )
+        .map(|s| top_crates(&s.stacks))
+        .map_err(|e| e.reason)
+    }
+
+    #[test]
+    fn taking_more_crates_than_a_stack_holds_is_an_error() {
+        assert_eq!(
+            checked("move 4 from 2 to 1", OnInvalid::Stop),
+            Err(IllegalMove::NotEnoughCrates {
+                stack: 2,
+                needed: 4
+            })
+        );
+    }
+
+    #[test]
+    fn stacks_outside_the_drawing_are_errors() {
+        assert_eq!(
+            checked("move 1 from 0 to 1", OnInvalid::Stop),
+            Err(IllegalMove::NoSuchStack { stack: 0 })
+        );
+        assert_eq!(
+            checked("move 1 from 1 to 4", OnInvalid::Stop),
+            Err(IllegalMove::NoSuchStack { stack: 4 })
+        );
+        assert_eq!(
+            checked("move one from 1 to 2", OnInvalid::Stop),
+            Err(IllegalMove::Unparseable)
+        );
+    }
+
+    #[test]
+    fn errors_name_the_input_line_and_stack_sizes() {
+        let (stack_data, _) = sample();
+        let stacks = parse_stack_data(stack_data).unwrap();
+        let instructions = "move 1 from 2 to 1\nmove 4 from 1 to 3\n";
+        let error = simulate_checked(&CrateMover9000, &stacks, instructions, 6, OnInvalid::Stop)
+            .unwrap_err();
+        assert_eq!(error.line, 7);
+        assert_eq!(error.stack_sizes, vec![3, 2, 1]);
+        assert_eq!(
+            error.to_string(),
+            "line 7 'move 4 from 1 to 3': can not take 4 crates from stack 1, \
+             it only has 3 (stack sizes 3 2 1)"
+        );
+    }
+
+    #[test]
+    fn skipped_moves_leave_the_stacks_alone() {
+        let (stack_data, instructions) = sample();
+        let with_bad_move =
+            instructions.replace("move 3 from 1", "move 9 from 1 to 3\nmove 3 from 1");
+        assert_eq!(
+            checked(&with_bad_move, OnInvalid::Skip),
+            Ok("CMZ".to_owned())
+        );
+        assert!(checked(&with_bad_move, OnInvalid::Stop).is_err());
+        let stacks = parse_stack_data(stack_data).unwrap();
+        let simulation = simulate_checked(
+            &CrateMover9000,
+            &stacks,
+            &with_bad_move,
+            first_instruction_line(stack_data),
+            OnInvalid::Skip,
+        )
+        .unwrap();
+        assert_eq!(simulation.skipped.len(), 1);
+        assert_eq!(simulation.skipped[0].line, 7);
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fmt,
+};
+
+use crate::{
+    crane::{self, CrateMover},
+    Crate, Move,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanError {
+    // Both drawings need the same number of stacks
+    StackCount { start: usize, goal: usize },
+    // Crates can only be moved, so both drawings need the same crates
+    DifferentCrates,
+    // Gave up after looking at this many arrangements
+    TooManyStates(usize),
+    // No moves lead to the goal, e.g. reordering the only stack
+    Unreachable,
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::StackCount { start, goal } => {
+                write!(f, "start has {start} stacks but the goal has {goal}")
+            }
+            PlanError::DifferentCrates => write!(f, "start and goal have different crates"),
+            PlanError::TooManyStates(n) => write!(f, "no plan found in {n} arrangements"),
+            PlanError::Unreachable => write!(f, "no moves lead from the start to the goal"),
+        }
+    }
+}
+
+// A lower bound on the moves still needed. A stack with a crate above the
+// part it shares with its goal has to be the source of some move, and a stack
+// missing crates from its goal has to be the target of one. Every move has one
+// source and one target, and changes each count by at most one.
+fn moves_needed(state: &[Vec<Crate>], goal: &[Vec<Crate>]) -> usize {
+    let mut sources = 0;
+    let mut targets = 0;
+    for (stack, wanted) in state.iter().zip(goal) {
+        let shared = stack.iter().zip(wanted).take_while(|(a, b)| a == b).count();
+        if stack.len() > shared {
+            sources += 1;
+        }
+        if wanted.len() > shared {
+            targets += 1;
+        }
+    }
+    sources.max(targets)
+}
+
+// Find a shortest list of moves that turns `start` into `goal` with the given
+// crane, looking at no more than `max_states` arrangements. This is an A*
+// search over whole arrangements of crates, so it is only practical for
+// small drawings.
+pub fn plan(
+    mover: &dyn CrateMover,
+    start: &[Vec<Crate>],
+    goal: &[Vec<Crate>],
+    max_states: usize,
+) -> Result<Vec<Move>, PlanError> {
+    search(mover, start, goal, max_states, moves_needed)
+}
+
+// Estimates the moves left from an arrangement to the goal
+type Estimate = fn(&[Vec<Crate>], &[Vec<Crate>]) -> usize;
+
+fn search(
+    mover: &dyn CrateMover,
+    start: &[Vec<Crate>],
+    goal: &[Vec<Crate>],
+    max_states: usize,
+    estimate: Estimate,
+) -> Result<Vec<Move>, PlanError> {
+    if start.len() != goal.len() {
+        return Err(PlanError::StackCount {
+            start: start.len(),
+            goal: goal.len(),
+        });
+    }
+    let mut start_crates = start.iter().flatten().collect::<Vec<_>>();
+    let mut goal_crates = goal.iter().flatten().collect::<Vec<_>>();
+    start_crates.sort();
+    goal_crates.sort();
+    if start_crates != goal_crates {
+        return Err(PlanError::DifferentCrates);
+    }
+
+    // Every arrangement seen so far, with the fewest moves found to reach it
+    // and the arrangement and move it was reached from
+    let mut states = vec![start.to_vec()];
+    let mut index = HashMap::from([(start.to_vec(), 0)]);
+    let mut best = vec![(0, None::<(usize, Move)>)];
+    let mut open = BinaryHeap::from([Reverse((estimate(start, goal), 0, 0))]);
+
+    while let Some(Reverse((_, moves, current))) = open.pop() {
+        if moves > best[current].0 {
+            continue;
+        }
+        if states[current] == goal {
+            return Ok(path_to(&best, current));
+        }
+        for from in 0..start.len() {
+            for to in (0..start.len()).filter(|&to| to != from) {
+                for n in 1..=states[current][from].len() {
+                    let mut next = states[current].clone();
+                    let (source, target) = crane::two_stacks(&mut next, from, to);
+                    crane::move_crates(mover, source, target, n);
+                    let op = Move {
+                        n,
+                        from: from + 1,
+                        to: to + 1,
+                    };
+                    let id = match index.get(&next) {
+                        Some(&id) if best[id].0 <= moves + 1 => continue,
+                        Some(&id) => id,
+                        None => {
+                            if states.len() == max_states {
+                                return Err(PlanError::TooManyStates(max_states));
+                            }
+                            index.insert(next.clone(), states.len());
+                            states.push(next);
+                            best.push((usize::MAX, None));
+                            states.len() - 1
+                        }
+                    };
+                    best[id] = (moves + 1, Some((current, op)));
+                    let priority = moves + 1 + estimate(&states[id], goal);
+                    open.push(Reverse((priority, moves + 1, id)));
+                }
+            }
+        }
+    }
+    Err(PlanError::Unreachable)
+}
+
+fn path_to(best: &[(usize, Option<(usize, Move)>)], mut state: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some((previous, op)) = best[state].1 {
+        moves.push(op);
+        state = previous;
+    }
+    moves.reverse();
+    moves
+}
+
+#[cfg(test)]
+mod planner_tests {
+    use super::*;
+    use crate::{
+        crane::{simulate_checked, CrateMover9000, CrateMover9001, OnInvalid},
+        parse_stack_data,
+    };
+
+    const LIMIT: usize = 100_000;
+
+    fn drawing(s: &str) -> Vec<Vec<Crate>> {
+        parse_stack_data(s).unwrap()
+    }
+
+    // Run the plan as puzzle instructions and check it reaches the goal
+    fn check(mover: &dyn CrateMover, start: &[Vec<Crate>], goal: &[Vec<Crate>]) -> usize {
+        let moves = plan(mover, start, goal, LIMIT).unwrap();
+        let instructions = moves.iter().map(|op| format!("{op}\n")).collect::<String>();
+        let simulation = simulate_checked(mover, start, &instructions, 1, OnInvalid::Stop).unwrap();
+        assert_eq!(simulation.stacks, goal);
+        let breadth_first = search(mover, start, goal, LIMIT, |_, _| 0).unwrap();
+        assert_eq!(moves.len(), breadth_first.len());
+        moves.len()
+    }
+
+    #[test]
+    fn the_sample_can_be_solved_in_fewer_moves() {
+        let (stack_data, _) = include_str!("../sample_input.txt")
+            .split_once("\n\n")
+            .unwrap();
+        let start = drawing(stack_data);
+        let after_9000 = drawing("        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3 ");
+        assert!(check(&CrateMover9000, &start, &after_9000) <= 4);
+        let after_9001 = drawing("        [D]\n        [N]\n        [Z]\n[M] [C] [P]\n 1   2   3 ");
+        assert!(check(&CrateMover9001, &start, &after_9001) <= 4);
+    }
+
+    #[test]
+    fn the_models_need_different_plans() {
+        let start = drawing("[A]\n[B]\n[C]\n 1   2");
+        let flipped = drawing("    [C]\n    [B]\n    [A]\n 1   2");
+        assert_eq!(check(&CrateMover9000, &start, &flipped), 1);
+        assert_eq!(check(&CrateMover9001, &start, &flipped), 3);
+        assert_eq!(
+            plan(&CrateMover9000, &start, &flipped, LIMIT).unwrap(),
+            vec![Move {
+                n: 3,
+                from: 1,
+                to: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn reaching_the_start_takes_no_moves() {
+        let start = drawing("[A] [B]\n 1   2");
+        assert_eq!(check(&CrateMover9001, &start, &start), 0);
+    }
+
+    #[test]
+    fn impossible_goals_are_reported() {
+        let start = drawing("[A] [B]\n 1   2");
+        assert_eq!(
+            plan(&CrateMover9000, &start, &drawing("[A] [C]\n 1   2"), LIMIT),
+            Err(PlanError::DifferentCrates)
+        );
+        assert_eq!(
+            plan(
+                &CrateMover9000,
+                &start,
+                &drawing("[A] [B]\n 1   2   3"),
+                LIMIT
+            ),
+            Err(PlanError::StackCount { start: 2, goal: 3 })
+        );
+        let goal = drawing("[B] [A]\n 1   2");
+        assert_eq!(
+            plan(&CrateMover9000, &start, &goal, 2),
+            Err(PlanError::TooManyStates(2))
+        );
+        // A single stack has nowhere to move crates to
+        let tower = drawing("[A]\n[B]\n 1");
+        let flipped = drawing("[B]\n[A]\n 1");
+        for mover in [&CrateMover9000 as &dyn CrateMover, &CrateMover9001] {
+            assert_eq!(
+                plan(mover, &tower, &flipped, LIMIT),
+                Err(PlanError::Unreachable)
+            );
+        }
+    }
+}
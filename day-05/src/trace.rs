@@ -0,0 +1,204 @@
+use crate::{
+    crane::{validate_heights, CrateMover, IllegalMove, MoveError, OnInvalid},
+    parse_move_op, Crate, Move,
+};
+
+// The top crate of every stack after all the moves, `None` for a stack that
+// ends up empty, and every instruction that was skipped on the way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tops {
+    pub tops: Vec<Option<Crate>>,
+    pub skipped: Vec<MoveError>,
+}
+
+impl Tops {
+    // Formatted like `crane::top_crates`
+    pub fn top_crates(&self) -> String {
+        self.tops
+            .iter()
+            .map(|top| top.as_deref().unwrap_or("?"))
+            .collect()
+    }
+}
+
+// Find the final top crates without moving any crates. A forward pass over the
+// instructions tracks only the height of each stack, which is enough to check
+// every move. Then each final top position is followed backwards through the
+// moves to the place in the drawing its crate started from. That is O(1) work
+// per move for each stack, with no copying, so it handles millions of moves.
+//
+// Returns `None` for crane models that can not be traced backwards.
+pub fn trace_tops(
+    mover: &dyn CrateMover,
+    stacks: &[Vec<Crate>],
+    instructions: &str,
+    first_line: usize,
+    on_invalid: OnInvalid,
+) -> Option<Result<Tops, MoveError>> {
+    mover.source_depth(1, 0)?;
+    Some(trace(mover, stacks, instructions, first_line, on_invalid))
+}
+
+fn trace(
+    mover: &dyn CrateMover,
+    stacks: &[Vec<Crate>],
+    instructions: &str,
+    first_line: usize,
+    on_invalid: OnInvalid,
+) -> Result<Tops, MoveError> {
+    let mut heights = stacks.iter().map(Vec::len).collect::<Vec<_>>();
+    let mut moves = Vec::new();
+    let mut skipped = Vec::new();
+    for (instruction, line) in instructions.lines().zip(first_line..) {
+        if instruction.trim().is_empty() {
+            continue;
+        }
+        let checked = parse_move_op(instruction)
+            .ok_or(IllegalMove::Unparseable)
+            .and_then(|op| validate_heights(&op, heights.len(), |i| heights[i]).map(|_| op));
+        match checked {
+            Ok(op) => {
+                if op.from != op.to {
+                    heights[op.from - 1] -= op.n;
+                    heights[op.to - 1] += op.n;
+                    moves.push(op);
+                }
+            }
+            Err(reason) => {
+                let error = MoveError {
+                    line,
+                    instruction: instruction.to_owned(),
+                    stack_sizes: heights.clone(),
+                    reason,
+                };
+                match on_invalid {
+                    OnInvalid::Stop => return Err(error),
+                    OnInvalid::Skip => skipped.push(error),
+                }
+            }
+        }
+    }
+
+    // Where each final top crate is: its stack (0-based) and depth from the top
+    let mut positions = heights
+        .iter()
+        .enumerate()
+        .filter(|&(_, &height)| height > 0)
+        .map(|(stack, _)| (stack, stack, 0))
+        .collect::<Vec<_>>();
+    for op in moves.iter().rev() {
+        trace_back(mover, op, &mut positions);
+    }
+
+    let mut tops = vec![None; stacks.len()];
+    for (end_stack, stack, depth) in positions {
+        let start = &stacks[stack];
+        tops[end_stack] = Some(start[start.len() - 1 - depth].clone());
+    }
+    Ok(Tops { tops, skipped })
+}
+
+// Undo one move for every tracked `(final stack, stack, depth)` position
+fn trace_back(mover: &dyn CrateMover, op: &Move, positions: &mut [(usize, usize, usize)]) {
+    let (from, to) = (op.from - 1, op.to - 1);
+    for (_, stack, depth) in positions.iter_mut() {
+        if *stack == to {
+            if *depth < op.n {
+                *stack = from;
+                *depth = mover
+                    .source_depth(op.n, *depth)
+                    .expect("Only traceable models are traced");
+            } else {
+                *depth -= op.n;
+            }
+        } else if *stack == from {
+            *depth += op.n;
+        }
+    }
+}
+
+#[cfg(test)]
+mod trace_tests {
+    use super::*;
+    use crate::{
+        bench::generate_input,
+        crane::{
+            simulate_checked, top_crates, CapacityLimited, CrateMover9000, CrateMover9001, TopHalf,
+        },
+        first_instruction_line, parse_stack_data,
+    };
+
+    fn both_ways(mover: &dyn CrateMover, input: &str, on_invalid: OnInvalid) -> (String, String) {
+        let (stack_data, instructions) = input.split_once("\n\n").unwrap();
+        let stacks = parse_stack_data(stack_data).unwrap();
+        let first_line = first_instruction_line(stack_data);
+        let naive = simulate_checked(mover, &stacks, instructions, first_line, on_invalid).unwrap();
+        let traced = trace_tops(mover, &stacks, instructions, first_line, on_invalid)
+            .unwrap()
+            .unwrap();
+        assert_eq!(traced.skipped, naive.skipped);
+        (traced.top_crates(), top_crates(&naive.stacks))
+    }
+
+    #[test]
+    fn the_sample_traces_to_the_same_answers() {
+        let sample = include_str!("../sample_input.txt");
+        assert_eq!(
+            both_ways(&CrateMover9000, sample, OnInvalid::Stop),
+            ("CMZ".to_owned(), "CMZ".to_owned())
+        );
+        assert_eq!(
+            both_ways(&CrateMover9001, sample, OnInvalid::Stop),
+            ("MCD".to_owned(), "MCD".to_owned())
+        );
+    }
+
+    #[test]
+    fn generated_inputs_agree_with_the_simulator() {
+        for seed in 1..20 {
+            let input = generate_input(7, 60, 2_000, seed);
+            let movers: [&dyn CrateMover; 4] = [
+                &CrateMover9000,
+                &CrateMover9001,
+                &CapacityLimited::new(2).unwrap(),
+                &CapacityLimited::new(5).unwrap(),
+            ];
+            for mover in movers {
+                let (traced, naive) = both_ways(mover, &input, OnInvalid::Stop);
+                assert_eq!(traced, naive, "{} with seed {seed}", mover.name());
+            }
+        }
+    }
+
+    #[test]
+    fn skipped_moves_are_skipped_in_both() {
+        let sample = include_str!("../sample_input.txt").replace(
+            "move 3 from 1 to 3",
+            "move 9 from 1 to 3\nmove 3 from 1 to 3",
+        );
+        let (traced, naive) = both_ways(&CrateMover9001, &sample, OnInvalid::Skip);
+        assert_eq!(traced, naive);
+    }
+
+    #[test]
+    fn emptied_stacks_have_no_top() {
+        let stacks = parse_stack_data("[A] [B]\n 1   2").unwrap();
+        let tops = trace_tops(
+            &CrateMover9000,
+            &stacks,
+            "move 1 from 1 to 2",
+            3,
+            OnInvalid::Stop,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(tops.tops, vec![None, Some("A".to_owned())]);
+        assert_eq!(tops.top_crates(), "?A");
+    }
+
+    #[test]
+    fn models_that_depend_on_stack_height_are_not_traced() {
+        let stacks = parse_stack_data("[A]\n 1").unwrap();
+        assert!(trace_tops(&TopHalf, &stacks, "", 3, OnInvalid::Stop).is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+
+use crate::stream::MarkerScanner;
+
+// A marker and the payload after it, which runs up to the marker of the next
+// frame of the same size or the end of the signal. Offsets count bytes from
+// the start of the signal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<'a> {
+    pub marker: usize,
+    pub offset: usize,
+    pub payload: &'a [u8],
+}
+
+impl Frame<'_> {
+    pub fn len(&self) -> usize {
+        self.payload.len()
+    }
+}
+
+impl fmt::Display for Frame<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "marker at {}, payload at {}, {} bytes '{}'",
+            self.marker,
+            self.offset,
+            self.len(),
+            String::from_utf8_lossy(self.payload)
+        )
+    }
+}
+
+// The signal split into packets at start-of-packet markers and into messages
+// at start-of-message markers. Anything before the first marker of a kind is
+// not part of any frame of that kind.
+//
+// Markers overlap wherever a run of different bytes goes on, so only some of
+// them start a frame. Once a frame has started, the run of different bytes
+// that made its marker has to break before another frame can start, and the
+// next marker must not reach back before the payload. Everything until then,
+// including more different bytes, is payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Framing<'a> {
+    pub packets: Vec<Frame<'a>>,
+    pub messages: Vec<Frame<'a>>,
+}
+
+pub fn frame(signal: &[u8]) -> Framing<'_> {
+    let [packets, messages] = frames(signal, [4, 14]);
+    Framing { packets, messages }
+}
+
+// Split the signal into frames for each window size at once. The signal must
+// not contain line breaks, which the marker scanner skips.
+pub fn frames<const N: usize>(signal: &[u8], window_sizes: [usize; N]) -> [Vec<Frame<'_>>; N] {
+    let mut marker_ends = [(); N].map(|_| Vec::<usize>::new());
+    // Per size, where its latest marker ended and whether the run of
+    // different bytes has broken since the latest frame started
+    let mut latest = [(0, false); N];
+    let mut scanner = MarkerScanner::new(&window_sizes);
+    scanner.feed(signal, |marker| {
+        let kind = window_sizes
+            .iter()
+            .position(|&size| size == marker.window)
+            .expect("Markers are one of the window sizes");
+        let (previous, broken) = &mut latest[kind];
+        *broken |= marker.position > *previous + 1;
+        *previous = marker.position;
+
+        let ends = &mut marker_ends[kind];
+        let starts_frame = match ends.last() {
+            None => true,
+            Some(&offset) => *broken && marker.position - marker.window >= offset,
+        };
+        if starts_frame {
+            ends.push(marker.position);
+            *broken = false;
+        }
+    });
+
+    let mut all_frames = [(); N].map(|_| Vec::new());
+    for ((frames, ends), size) in all_frames.iter_mut().zip(&marker_ends).zip(window_sizes) {
+        for (i, &offset) in ends.iter().enumerate() {
+            let end = ends.get(i + 1).map_or(signal.len(), |next| next - size);
+            frames.push(Frame {
+                marker: offset - size,
+                offset,
+                payload: &signal[offset..end],
+            });
+        }
+    }
+    all_frames
+}
+
+#[cfg(test)]
+mod framing_tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../sample-input.txt");
+
+    fn frame_at(marker: usize, offset: usize, payload: &str) -> Frame<'_> {
+        Frame {
+            marker,
+            offset,
+            payload: payload.as_bytes(),
+        }
+    }
+
+    #[test]
+    fn sample_has_one_packet() {
+        let framing = frame(SAMPLE.trim_end().as_bytes());
+        assert_eq!(
+            framing.packets,
+            vec![frame_at(3, 7, "gbljsphdztnvjfqwrcgsmlb")]
+        );
+    }
+
+    #[test]
+    fn sample_has_one_message() {
+        let framing = frame(SAMPLE.trim_end().as_bytes());
+        assert_eq!(framing.messages, vec![frame_at(5, 19, "jfqwrcgsmlb")]);
+        assert_eq!(
+            framing.messages[0].to_string(),
+            "marker at 5, payload at 19, 11 bytes 'jfqwrcgsmlb'"
+        );
+    }
+
+    #[test]
+    fn payloads_run_to_the_next_frame() {
+        // `bcdx` carries on the first marker's run, so it is payload. `xefg`
+        // comes after the run breaks at `xx` and starts the next frame.
+        let [packets] = frames(b"abcdxxefghyyzz", [4]);
+        assert_eq!(packets, vec![frame_at(0, 4, "x"), frame_at(5, 9, "hyyzz")]);
+    }
+
+    #[test]
+    fn payloads_can_hold_markers() {
+        // `cdbe` and `dbef` come after the run breaks at `bcdb` but reach back
+        // before the payload, so the next frame waits for `fghi`
+        let [packets] = frames(b"abcdbeffghij", [4]);
+        assert_eq!(packets, vec![frame_at(0, 4, "bef"), frame_at(7, 11, "j")]);
+        let [packets] = frames(b"abcdaaaaefghaaaa", [4]);
+        assert_eq!(
+            packets,
+            vec![frame_at(0, 4, "aaa"), frame_at(7, 11, "haaaa")]
+        );
+    }
+
+    #[test]
+    fn no_markers_no_frames() {
+        let framing = frame(b"abcabcabc");
+        assert!(framing.packets.is_empty());
+        assert!(framing.messages.is_empty());
+    }
+}
@@ -1,43 +1,171 @@
-use std::{fs, io};
-
-fn find_duplicates(input: &str, seg_len: usize) -> usize {
-    let mut index_found = false;
-    let mut i = 0;
-    while i < input.len() - seg_len + 1 && !index_found {
-        let segment = &input[i..i + seg_len];
-        let mut duplicate_found = false;
-        let mut j = 0;
-        while j < segment.len() && !duplicate_found {
-            let curr = segment
-                .chars()
-                .nth(j)
-                .expect("index can never be out of range");
-            let mut inner_iter = segment[j + 1..].chars();
-            while let (Some(c), true) = (inner_iter.next(), !duplicate_found) {
-                duplicate_found = curr == c;
-            }
-            j += 1;
-        }
-        index_found = !duplicate_found;
-        i += 1
+use std::{
+    env, fs,
+    io::{self, BufRead},
+};
+
+mod framing;
+mod stream;
+mod window;
+
+use window::{distinct_windows, first_distinct_byte_window, first_distinct_window};
+
+// Markers are found in the signal's bytes, so a multi-byte character counts
+// as several symbols
+fn part_1(input: &str) -> Option<usize> {
+    first_distinct_byte_window(input.as_bytes(), 4)
+}
+
+fn part_2(input: &str) -> Option<usize> {
+    first_distinct_byte_window(input.as_bytes(), 14)
+}
+
+fn show(marker: Option<usize>) -> String {
+    marker.map_or("no marker".to_owned(), |position| position.to_string())
+}
+
+// What the puzzle calls markers of each length
+fn marker_name(window: usize) -> String {
+    match window {
+        4 => "start-of-packet".to_owned(),
+        14 => "start-of-message".to_owned(),
+        _ => format!("{window}-byte"),
     }
-    i + seg_len - 1
 }
 
-fn part_1(input: &str) -> usize {
-    find_duplicates(input, 4)
+// Report every marker in the signal on stdin as soon as it is read
+fn run_stream(window_sizes: &[usize]) -> io::Result<()> {
+    let length = stream::scan(io::stdin().lock(), window_sizes, |marker| {
+        println!(
+            "{} marker at {}",
+            marker_name(marker.window),
+            marker.position
+        );
+    })?;
+    println!("day-06;stream length = {length}");
+    Ok(())
 }
 
-fn part_2(input: &str) -> usize {
-    find_duplicates(input, 14)
+// Treat each line of stdin as one token, such as a log record or a sensor id,
+// and look for runs of `window` different tokens
+fn run_records(window: usize) -> io::Result<()> {
+    let records = io::stdin().lock().lines().collect::<io::Result<Vec<_>>>()?;
+    let first = first_distinct_window(&records, window);
+    println!("day-06;records first window = {}", show(first));
+    println!(
+        "day-06;records windows = {}",
+        distinct_windows(&records, window).count()
+    );
+    Ok(())
+}
+
+// Split the signal into packets and messages and report each one
+fn run_frames(signal: &str) {
+    let framing = framing::frame(signal.as_bytes());
+    for (window, frames) in [(4, &framing.packets), (14, &framing.messages)] {
+        for (i, frame) in frames.iter().enumerate() {
+            println!("{} {}: {frame}", marker_name(window), i + 1);
+        }
+    }
+    println!(
+        "day-06;frames = {} packets, {} messages",
+        framing.packets.len(),
+        framing.messages.len()
+    );
+}
+
+// A window size given on the command line. The streaming commands need at
+// least one byte per marker, the others accept an empty window.
+fn parse_window(arg: &str, allow_empty: bool) -> io::Result<usize> {
+    match arg.parse::<usize>() {
+        Ok(window) if window > 0 || allow_empty => Ok(window),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid window size '{arg}', expected a positive number"),
+        )),
+    }
 }
 
 fn main() -> io::Result<()> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("stream") {
+        let mut window_sizes = args[1..]
+            .iter()
+            .map(|s| parse_window(s, false))
+            .collect::<io::Result<Vec<_>>>()?;
+        if window_sizes.is_empty() {
+            window_sizes = vec![4, 14];
+        }
+        return run_stream(&window_sizes);
+    }
+    if args.first().map(String::as_str) == Some("records") {
+        let window = args.get(1).map_or(Ok(4), |s| parse_window(s, true))?;
+        return run_records(window);
+    }
+
     let input = fs::read_to_string("input.txt")?;
-    let result_1 = part_1(&input);
-    println!("day-06;part-1 = {}", result_1);
+    let signal = input.trim_end();
+    if args.first().map(String::as_str) == Some("frames") {
+        run_frames(signal);
+        return Ok(());
+    }
+    let result_1 = part_1(signal);
+    println!("day-06;part-1 = {}", show(result_1));
 
-    let result_2 = part_2(&input);
-    println!("day-06;part-2 = {}", result_2);
+    let result_2 = part_2(signal);
+    println!("day-06;part-2 = {}", show(result_2));
     Ok(())
 }
+
+#[cfg(test)]
+mod day_06_tests {
+    use crate::{part_1, part_2, window::first_distinct_byte_window};
+
+    const SAMPLE: &str = include_str!("../sample-input.txt");
+
+    // The other examples from the puzzle, with both answers
+    const EXAMPLES: [(&str, usize, usize); 4] = [
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    #[test]
+    fn sample_part_1() {
+        assert_eq!(part_1(SAMPLE.trim_end()), Some(7));
+    }
+
+    #[test]
+    fn sample_part_2() {
+        assert_eq!(part_2(SAMPLE.trim_end()), Some(19));
+    }
+
+    #[test]
+    fn puzzle_examples() {
+        for (signal, packet, message) in EXAMPLES {
+            assert_eq!(part_1(signal), Some(packet), "{signal}");
+            assert_eq!(part_2(signal), Some(message), "{signal}");
+        }
+    }
+
+    #[test]
+    fn short_or_repetitive_input_has_no_marker() {
+        assert_eq!(part_1(""), None);
+        assert_eq!(part_1("abc"), None);
+        assert_eq!(part_2("abababababababababab"), None);
+    }
+
+    #[test]
+    fn a_marker_can_end_the_input() {
+        assert_eq!(part_1("aaabcd"), Some(6));
+        assert_eq!(first_distinct_byte_window(b"a", 1), Some(1));
+        assert_eq!(first_distinct_byte_window(b"xyz", 0), Some(0));
+    }
+
+    #[test]
+    fn non_ascii_input_is_read_as_bytes() {
+        // 'é' is two bytes, so these are five different bytes
+        assert_eq!(part_1("aaéb"), Some(5));
+        assert_eq!(part_1("ééé"), None);
+    }
+}
@@ -0,0 +1,168 @@
+use std::io::{self, Read};
+
+use crate::window::{ByteTable, DistinctRun};
+
+// A marker of `window` bytes that ends after `position` bytes of the signal,
+// as found by `window::DistinctWindows`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub window: usize,
+    pub position: usize,
+}
+
+// Finds markers for several window sizes at once as bytes arrive, at the same
+// positions `window::distinct_byte_windows` finds them for each size. Every
+// size is a threshold on one shared run of different bytes, so memory use
+// grows with neither the signal nor the number of sizes.
+#[derive(Debug, Clone)]
+pub struct MarkerScanner {
+    run: DistinctRun<ByteTable>,
+    window_sizes: Vec<usize>,
+}
+
+impl MarkerScanner {
+    pub fn new(window_sizes: &[usize]) -> MarkerScanner {
+        assert!(
+            window_sizes.iter().all(|&size| size > 0),
+            "Markers are at least one byte long"
+        );
+        MarkerScanner {
+            run: DistinctRun::new(ByteTable::new()),
+            window_sizes: window_sizes.to_vec(),
+        }
+    }
+
+    // Number of signal bytes seen so far
+    pub fn position(&self) -> usize {
+        self.run.read()
+    }
+
+    // Feed the next part of the signal, calling `on_marker` for each marker
+    // in the order they end. Line breaks are not part of the signal.
+    pub fn feed(&mut self, bytes: &[u8], mut on_marker: impl FnMut(Marker)) {
+        for &byte in bytes {
+            if byte == b'\n' || byte == b'\r' {
+                continue;
+            }
+            let run = self.run.push(byte);
+            for &window in &self.window_sizes {
+                if run >= window {
+                    on_marker(Marker {
+                        window,
+                        position: self.run.read(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Read the whole signal from `reader` a block at a time, calling `on_marker`
+// for every marker as soon as it is found. Returns the length of the signal.
+pub fn scan(
+    mut reader: impl Read,
+    window_sizes: &[usize],
+    mut on_marker: impl FnMut(Marker),
+) -> io::Result<usize> {
+    const BLOCK_SIZE: usize = 64 * 1024;
+    let mut scanner = MarkerScanner::new(window_sizes);
+    let mut block = vec![0; BLOCK_SIZE];
+    loop {
+        let read = match reader.read(&mut block) {
+            Ok(0) => return Ok(scanner.position()),
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        scanner.feed(&block[..read], &mut on_marker);
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use super::*;
+    use crate::window::distinct_byte_windows;
+
+    fn markers(signal: &[u8], window_sizes: &[usize]) -> Vec<Marker> {
+        let mut found = Vec::new();
+        scan(signal, window_sizes, |marker| found.push(marker)).unwrap();
+        found
+    }
+
+    // A repeatable signal over 16 letters, so markers are neither rare nor
+    // everywhere
+    fn signal(len: usize) -> Vec<u8> {
+        let mut state = 2022u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                b'a' + (state >> 16) as u8 % 16
+            })
+            .collect()
+    }
+
+    fn positions(signal: &[u8], window: usize) -> Vec<usize> {
+        markers(signal, &[window])
+            .iter()
+            .map(|m| m.position)
+            .collect()
+    }
+
+    #[test]
+    fn every_marker_matches_the_sliding_window() {
+        let signal = signal(5_000);
+        for window in [1, 4, 9, 14] {
+            assert_eq!(
+                positions(&signal, window),
+                distinct_byte_windows(&signal, window).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn markers_can_overlap() {
+        assert_eq!(positions(b"abcdabcd", 4), vec![4, 5, 6, 7, 8]);
+        assert_eq!(positions(b"abcabcdd", 4), vec![7]);
+        assert_eq!(positions(b"aaaa", 2), vec![]);
+    }
+
+    #[test]
+    fn several_window_sizes_in_one_pass() {
+        let found = markers(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb", &[4, 14]);
+        assert_eq!(
+            found[..2],
+            [
+                Marker {
+                    window: 4,
+                    position: 7
+                },
+                Marker {
+                    window: 4,
+                    position: 8
+                },
+            ]
+        );
+        assert!(found.contains(&Marker {
+            window: 14,
+            position: 19
+        }));
+    }
+
+    #[test]
+    fn block_boundaries_do_not_matter() {
+        let signal = signal(200_000);
+        let whole = markers(&signal, &[4, 14]);
+        let mut scanner = MarkerScanner::new(&[4, 14]);
+        let mut byte_at_a_time = Vec::new();
+        for byte in signal.chunks(1) {
+            scanner.feed(byte, |marker| byte_at_a_time.push(marker));
+        }
+        assert_eq!(byte_at_a_time, whole);
+    }
+
+    #[test]
+    fn line_breaks_are_skipped() {
+        assert_eq!(positions(b"abc\nd\r\n", 4), vec![4]);
+        assert_eq!(scan(&b"ab\ncd\n"[..], &[4], |_| {}).unwrap(), 4);
+    }
+}
@@ -0,0 +1,211 @@
+use std::{collections::HashMap, hash::Hash, iter::Copied, slice};
+
+// Where each token was last seen, by its index in the stream
+pub trait LastSeen<T> {
+    // Record that `token` is at `index`, returning where it was seen before
+    fn replace(&mut self, token: T, index: usize) -> Option<usize>;
+}
+
+impl<T: Hash + Eq> LastSeen<T> for HashMap<T, usize> {
+    fn replace(&mut self, token: T, index: usize) -> Option<usize> {
+        self.insert(token, index)
+    }
+}
+
+// A table with a slot for every byte, so bytes need no hashing. Slots hold
+// one more than the index, leaving 0 for bytes not seen yet.
+#[derive(Debug, Clone)]
+pub struct ByteTable([usize; 256]);
+
+impl ByteTable {
+    pub fn new() -> ByteTable {
+        ByteTable([0; 256])
+    }
+}
+
+impl LastSeen<u8> for ByteTable {
+    fn replace(&mut self, token: u8, index: usize) -> Option<usize> {
+        let previous = std::mem::replace(&mut self.0[token as usize], index + 1);
+        previous.checked_sub(1)
+    }
+}
+
+// The run of different tokens that ends at the latest token read.
+//
+// Only the last time each token was seen is kept. The tokens after the most
+// recent repeat are all different, so the run starts just after it. That is
+// O(1) per token and tokens never need to be cloned.
+#[derive(Debug, Clone)]
+pub struct DistinctRun<S> {
+    seen: S,
+    read: usize,
+    // Index of the first token after the most recent repeat
+    start: usize,
+}
+
+impl<S> DistinctRun<S> {
+    pub fn new(seen: S) -> DistinctRun<S> {
+        DistinctRun {
+            seen,
+            read: 0,
+            start: 0,
+        }
+    }
+
+    // Number of tokens read so far
+    pub fn read(&self) -> usize {
+        self.read
+    }
+
+    // Read the next token, returning how many tokens the run now has
+    pub fn push<T>(&mut self, token: T) -> usize
+    where
+        S: LastSeen<T>,
+    {
+        if let Some(previous) = self.seen.replace(token, self.read) {
+            self.start = self.start.max(previous + 1);
+        }
+        self.read += 1;
+        self.read - self.start
+    }
+}
+
+// Every position in a stream where the last `window` tokens are all
+// different, as the number of tokens read up to and including that window.
+// This is what a marker is throughout day 06: a window ends at each position
+// the run of different tokens is at least `window` long, so markers of the
+// same size can overlap. An empty window is distinct everywhere, including
+// before the first token.
+pub struct DistinctWindows<I, S> {
+    tokens: I,
+    run: DistinctRun<S>,
+    window: usize,
+    // Whether the empty window before the first token is still to be yielded
+    at_start: bool,
+}
+
+impl<I, S> Iterator for DistinctWindows<I, S>
+where
+    I: Iterator,
+    S: LastSeen<I::Item>,
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.at_start {
+            self.at_start = false;
+            return Some(0);
+        }
+        for token in self.tokens.by_ref() {
+            if self.run.push(token) >= self.window {
+                return Some(self.run.read());
+            }
+        }
+        None
+    }
+}
+
+fn windows_with<I, S>(tokens: I, window: usize, seen: S) -> DistinctWindows<I, S> {
+    DistinctWindows {
+        tokens,
+        run: DistinctRun::new(seen),
+        window,
+        at_start: window == 0,
+    }
+}
+
+pub fn distinct_windows<T, I>(
+    tokens: I,
+    window: usize,
+) -> DistinctWindows<I::IntoIter, HashMap<T, usize>>
+where
+    T: Hash + Eq,
+    I: IntoIterator<Item = T>,
+{
+    windows_with(tokens.into_iter(), window, HashMap::new())
+}
+
+pub fn distinct_byte_windows(
+    bytes: &[u8],
+    window: usize,
+) -> DistinctWindows<Copied<slice::Iter<'_, u8>>, ByteTable> {
+    windows_with(bytes.iter().copied(), window, ByteTable::new())
+}
+
+// Tokens read up to the end of the first window of `window` different tokens
+pub fn first_distinct_window<T, I>(tokens: I, window: usize) -> Option<usize>
+where
+    T: Hash + Eq,
+    I: IntoIterator<Item = T>,
+{
+    distinct_windows(tokens, window).next()
+}
+
+// As `first_distinct_window`, with each byte a token
+pub fn first_distinct_byte_window(bytes: &[u8], window: usize) -> Option<usize> {
+    distinct_byte_windows(bytes, window).next()
+}
+
+#[cfg(test)]
+mod window_tests {
+    use super::*;
+
+    #[test]
+    fn any_hashable_tokens() {
+        let sensors = [7u32, 3, 7, 1, 9, 3, 3];
+        assert_eq!(first_distinct_window(sensors, 3), Some(4));
+        let records = "GET PUT GET GET POST PUT DELETE".split(' ');
+        assert_eq!(first_distinct_window(records, 4), Some(7));
+        assert_eq!(first_distinct_window(Vec::<String>::new(), 1), None);
+    }
+
+    #[test]
+    fn tokens_are_moved_not_cloned() {
+        struct Record(u8);
+        impl PartialEq for Record {
+            fn eq(&self, other: &Record) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Eq for Record {}
+        impl Hash for Record {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+        let records = [1, 1, 2, 3].map(Record);
+        assert_eq!(first_distinct_window(records, 3), Some(4));
+    }
+
+    #[test]
+    fn every_window_is_reported() {
+        let windows = distinct_byte_windows(b"mjqjpqmgbljsph", 4).collect::<Vec<_>>();
+        assert_eq!(windows, vec![7, 8, 9, 10, 11, 12, 13, 14]);
+        assert_eq!(
+            distinct_windows("abab".chars(), 2).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            distinct_windows("aab".chars(), 0).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn bytes_agree_with_hashed_tokens() {
+        let signal = b"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsgzcfzfwzzqfrljwzlrfnpqdbhtmscgvjw";
+        for window in 1..=16 {
+            assert_eq!(
+                distinct_byte_windows(signal, window).collect::<Vec<_>>(),
+                distinct_windows(signal.iter(), window).collect::<Vec<_>>(),
+                "window {window}"
+            );
+        }
+    }
+
+    #[test]
+    fn chars_are_one_token_each() {
+        assert_eq!(first_distinct_window("ééab".chars(), 3), Some(4));
+        assert_eq!(first_distinct_byte_window("ééab".as_bytes(), 3), Some(5));
+    }
+}
@@ -1,145 +1,177 @@
-use std::{collections::HashMap, fs, io};
+use std::{env, fmt, fs, io};
 
-#[derive(Debug, Default)]
-struct Dir {
-    size: u32,
-    children: Vec<String>,
-    parent: Option<String>,
-}
-
-impl Dir {
-    fn with_parent(parent: &str) -> Dir {
-        Self {
-            parent: Some(parent.to_owned()),
-            ..Default::default()
-        }
-    }
-}
+mod vfs;
 
-fn total_dir_size(directory: &str, file_system: &HashMap<String, Dir>) -> u32 {
-    let dir = &file_system[directory];
-    let contained: u32 = dir
-        .children
-        .iter()
-        .map(|d| total_dir_size(d, file_system))
-        .sum();
-    dir.size + contained
-}
+use vfs::{FileSystem, NameTaken, NodeId, NodeKind};
 
-fn is_cd_instruction(line: &str) -> bool {
-    line.starts_with("$ cd")
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TranscriptErrorKind {
+    UnknownCommand(String),
+    MissingPath,
+    NoSuchDir(String),
+    NotADir(String),
+    BadEntry(String),
+    NameTaken(NameTaken),
 }
 
-fn is_ls_instruction(line: &str) -> bool {
-    line.starts_with("$ ls")
+// A transcript line that could not be followed, numbered from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TranscriptError {
+    line: usize,
+    kind: TranscriptErrorKind,
 }
 
-fn is_dir_description(line: &str) -> bool {
-    line.starts_with("dir")
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            TranscriptErrorKind::UnknownCommand(command) => {
+                write!(f, "unknown command '{command}'")
+            }
+            TranscriptErrorKind::MissingPath => write!(f, "cd must be followed by a path"),
+            TranscriptErrorKind::NoSuchDir(path) => write!(f, "no directory {path}"),
+            TranscriptErrorKind::NotADir(path) => write!(f, "{path} is not a directory"),
+            TranscriptErrorKind::BadEntry(entry) => {
+                write!(f, "'{entry}' is not 'dir name' or 'size name'")
+            }
+            TranscriptErrorKind::NameTaken(taken) => write!(f, "{taken}"),
+        }
+    }
 }
 
-fn change_dir(current_dir_name: &str, line: &str, file_system: &HashMap<String, Dir>) -> String {
-    let new_segment = line
-        .split_whitespace()
-        .rev()
-        .next()
-        .expect("cd must be followed by dir name");
-
-    match new_segment {
-        "/" => new_segment.to_owned(),
-        ".." => {
-            let dir = file_system
-                .get(current_dir_name)
-                .unwrap_or_else(|| panic!("{current_dir_name} is not in file_system"));
-            dir.parent
-                .as_ref()
-                .map(String::from)
-                .unwrap_or_else(|| panic!("{current_dir_name} has no parent"))
-        }
-        _ => {
-            let mut new_dir = String::from(current_dir_name);
-            new_dir.push_str(new_segment);
-            new_dir.push('/');
-            new_dir
-        }
+impl From<NameTaken> for TranscriptErrorKind {
+    fn from(taken: NameTaken) -> TranscriptErrorKind {
+        TranscriptErrorKind::NameTaken(taken)
     }
 }
 
-fn add_new_dir(current_dir_name: &str, line: &str, file_system: &mut HashMap<String, Dir>) {
-    let dir_name = line
-        .split_whitespace()
-        .rev()
-        .next()
-        .expect("Dir format is 'dir dir_name'");
-    let mut full_child_path = String::from(current_dir_name);
-    full_child_path.push_str(dir_name);
-    full_child_path.push('/');
-    let current_dir = file_system
-        .get_mut(current_dir_name)
-        .unwrap_or_else(|| panic!("{current_dir_name} is not in file_system"));
-    current_dir.children.push(String::from(&full_child_path));
-    file_system.insert(full_child_path, Dir::with_parent(current_dir_name));
+// Paths can be absolute or have several segments, like `cd /a/b` or
+// `cd ../c`, but must lead to a directory already listed
+fn change_dir(
+    current_dir: NodeId,
+    path: &str,
+    file_system: &FileSystem,
+) -> Result<NodeId, TranscriptErrorKind> {
+    let dir = file_system
+        .resolve(current_dir, path)
+        .ok_or_else(|| TranscriptErrorKind::NoSuchDir(path.to_owned()))?;
+    if !file_system.node(dir).is_dir() {
+        return Err(TranscriptErrorKind::NotADir(file_system.path(dir)));
+    }
+    Ok(dir)
 }
 
-fn update_dir_size(current_dir_name: &str, line: &str, file_system: &mut HashMap<String, Dir>) {
-    // Line must represent a filename
-    let (filesize, _filename) = line
-        .split_once(char::is_whitespace)
-        .expect("Filename format is 'filesize filename");
-    let size = filesize.parse::<u32>().expect("Filesize is a valid u32");
-    let mut dir = file_system
-        .get_mut(current_dir_name)
-        .expect("Current dir is in filesystem");
-    dir.size += size;
+// One line of `ls` output. Entries already in the directory are reused, so
+// listing the same directory again does not count its files twice.
+fn add_entry(
+    current_dir: NodeId,
+    line: &str,
+    file_system: &mut FileSystem,
+) -> Result<(), TranscriptErrorKind> {
+    let bad_entry = || TranscriptErrorKind::BadEntry(line.to_owned());
+    let (size_or_dir, name) = line.split_once(' ').ok_or_else(bad_entry)?;
+    if name.is_empty() {
+        return Err(bad_entry());
+    }
+    if size_or_dir == "dir" {
+        file_system.add_dir(current_dir, name)?;
+    } else {
+        let size = size_or_dir.parse::<u32>().map_err(|_| bad_entry())?;
+        file_system.add_file(current_dir, name, size)?;
+    }
+    Ok(())
 }
 
-fn parse_file_system_from_transctipt(transcript: &str) -> HashMap<String, Dir> {
-    let mut file_system = HashMap::<String, Dir>::new();
-    file_system.insert("/".to_owned(), Dir::default());
-    let mut current_dir_name = "".to_owned();
+fn parse_file_system_from_transctipt(transcript: &str) -> Result<FileSystem, TranscriptError> {
+    let mut file_system = FileSystem::new();
+    let mut current_dir = FileSystem::ROOT;
 
-    for line in transcript.lines() {
-        if is_cd_instruction(line) {
-            current_dir_name = change_dir(&current_dir_name, line, &file_system);
-        } else if is_ls_instruction(line) {
+    for (i, line) in transcript.lines().enumerate() {
+        let error = |kind| TranscriptError { line: i + 1, kind };
+        let Some(command) = line.strip_prefix('$') else {
+            add_entry(current_dir, line, &mut file_system).map_err(error)?;
             continue;
-        } else if is_dir_description(line) {
-            add_new_dir(&current_dir_name, line, &mut file_system);
-        } else {
-            update_dir_size(&current_dir_name, line, &mut file_system);
+        };
+        let mut words = command.split_whitespace();
+        match words.next() {
+            Some("cd") => {
+                let path = words
+                    .next()
+                    .ok_or_else(|| error(TranscriptErrorKind::MissingPath))?;
+                current_dir = change_dir(current_dir, path, &file_system).map_err(error)?;
+            }
+            Some("ls") => {}
+            _ => {
+                return Err(error(TranscriptErrorKind::UnknownCommand(
+                    command.trim().to_owned(),
+                )))
+            }
         }
     }
+    Ok(file_system)
+}
+
+// The total size of every directory
+fn dir_sizes(file_system: &FileSystem) -> impl Iterator<Item = u32> + '_ {
+    let sizes = file_system.total_sizes();
     file_system
+        .walk(FileSystem::ROOT)
+        .filter(|&id| file_system.node(id).is_dir())
+        .map(move |id| sizes[id])
 }
 
-fn part_1(file_system: &HashMap<String, Dir>) -> u32 {
+fn part_1(file_system: &FileSystem) -> u32 {
     const SIZE_LIMIT: u32 = 100000;
-    file_system
-        .keys()
-        .map(|name| total_dir_size(name, file_system))
+    dir_sizes(file_system)
         .filter(|&size| size <= SIZE_LIMIT)
         .sum()
 }
 
-fn part_2(file_system: &HashMap<String, Dir>) -> u32 {
+fn part_2(file_system: &FileSystem) -> u32 {
     const TOTAL_DISK_SPACE: u32 = 70000000;
     const REQUIRED_SPACE: u32 = 30000000;
-    let used_space = total_dir_size("/", file_system);
+    let used_space = file_system.total_sizes()[FileSystem::ROOT];
     let free_space = TOTAL_DISK_SPACE - used_space;
     let need_to_free = REQUIRED_SPACE - free_space;
-    file_system
-        .keys()
-        .map(|name| total_dir_size(name, file_system))
+    dir_sizes(file_system)
         .filter(|&size| size >= need_to_free)
         .min()
         .expect("At least one file exists that can be deleted")
 }
 
-#[allow(unused)]
+// List a directory the way `ls` shows it in the transcript
+fn list(file_system: &FileSystem, path: &str) {
+    let Some(dir) = file_system.resolve(FileSystem::ROOT, path) else {
+        println!("{path} is not in file_system");
+        return;
+    };
+    for &id in file_system.children(dir) {
+        let node = file_system.node(id);
+        match node.kind {
+            NodeKind::Dir { .. } => println!("dir {}", node.name),
+            NodeKind::File { size } => println!("{size} {}", node.name),
+        }
+    }
+}
+
 fn main() -> io::Result<()> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
     let input = fs::read_to_string("input.txt")?;
 
-    let file_system = parse_file_system_from_transctipt(&input);
+    let file_system = parse_file_system_from_transctipt(&input)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+    match args.first().map(String::as_str) {
+        Some("tree") => {
+            print!("{file_system}");
+            return Ok(());
+        }
+        Some("ls") => {
+            list(&file_system, args.get(1).map_or("/", String::as_str));
+            return Ok(());
+        }
+        _ => {}
+    }
 
     let part_1_result = part_1(&file_system);
     println!("day-07;part-1 = {part_1_result}");
@@ -148,3 +180,88 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod day_07_tests {
+    use crate::{
+        parse_file_system_from_transctipt, part_1, part_2, vfs::FileSystem, TranscriptError,
+        TranscriptErrorKind,
+    };
+
+    const SAMPLE: &str = include_str!("../sample-input.txt");
+
+    #[test]
+    fn sample_part_1() {
+        let file_system = parse_file_system_from_transctipt(SAMPLE).unwrap();
+        assert_eq!(part_1(&file_system), 95437);
+    }
+
+    #[test]
+    fn sample_part_2() {
+        let file_system = parse_file_system_from_transctipt(SAMPLE).unwrap();
+        assert_eq!(part_2(&file_system), 24933642);
+    }
+
+    #[test]
+    fn sample_keeps_file_names() {
+        let file_system = parse_file_system_from_transctipt(SAMPLE).unwrap();
+        let i = file_system.resolve(FileSystem::ROOT, "/a/e/i").unwrap();
+        assert_eq!(file_system.path(i), "/a/e/i");
+        let sizes = file_system.total_sizes();
+        assert_eq!(sizes[i], 584);
+        let d = file_system.resolve(i, "../../../d").unwrap();
+        assert_eq!(sizes[d], 24933642);
+        assert!(file_system
+            .to_string()
+            .starts_with("- / (dir)\n  - a (dir)\n    - e (dir)\n      - i (file, size=584)\n"));
+    }
+
+    #[test]
+    fn listing_again_changes_nothing() {
+        let transcript = SAMPLE.replace("$ cd d\n$ ls\n", "$ cd d\n$ ls\n$ ls\ndir a\n$ ls\n");
+        let transcript = format!("{transcript}$ cd /\n$ ls\ndir a\n14848514 b.txt\n");
+        let file_system = parse_file_system_from_transctipt(&transcript).unwrap();
+        assert_eq!(part_1(&file_system), 95437);
+        assert_eq!(part_2(&file_system), 24933642);
+        assert_eq!(file_system.children(FileSystem::ROOT).len(), 4);
+    }
+
+    #[test]
+    fn cd_follows_absolute_and_nested_paths() {
+        let transcript = format!("{SAMPLE}$ cd /a/e\n$ ls\n16 j\n$ cd ../../d\n$ ls\n1 l\n");
+        let file_system = parse_file_system_from_transctipt(&transcript).unwrap();
+        let sizes = file_system.total_sizes();
+        let e = file_system.resolve(FileSystem::ROOT, "/a/e").unwrap();
+        assert_eq!(sizes[e], 600);
+        assert!(file_system.resolve(FileSystem::ROOT, "/d/l").is_some());
+    }
+
+    #[test]
+    fn problems_are_reported_with_line_numbers() {
+        let error = |transcript: &str| parse_file_system_from_transctipt(transcript).unwrap_err();
+        assert_eq!(
+            error("$ cd /\n$ ls\ndir a\n$ rm a\n"),
+            TranscriptError {
+                line: 4,
+                kind: TranscriptErrorKind::UnknownCommand("rm a".to_owned())
+            }
+        );
+        assert_eq!(
+            error("$ cd /\n$ cd a\n").to_string(),
+            "line 2: no directory a"
+        );
+        assert_eq!(
+            error("$ ls\n1 a\n$ cd /a\n").to_string(),
+            "line 3: /a is not a directory"
+        );
+        assert_eq!(
+            error("$ ls\n1 a\ndir a\n").to_string(),
+            "line 3: /a already exists"
+        );
+        assert_eq!(
+            error("$ ls\nbig a\n").to_string(),
+            "line 2: 'big a' is not 'dir name' or 'size name'"
+        );
+        assert_eq!(error("$ cd\n").kind, TranscriptErrorKind::MissingPath);
+    }
+}
@@ -0,0 +1,299 @@
+use std::fmt;
+
+// Nodes are kept in one list and refer to each other by their index in it
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    Dir { children: Vec<NodeId> },
+    File { size: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub kind: NodeKind,
+}
+
+// A directory and a file cannot share a name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameTaken {
+    pub path: String,
+}
+
+impl fmt::Display for NameTaken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} already exists", self.path)
+    }
+}
+
+impl Node {
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, NodeKind::Dir { .. })
+    }
+}
+
+// An in-memory tree of directories and files, starting from `/`. A node is
+// always added after its parent, so every parent has a smaller index than its
+// children.
+#[derive(Debug, Clone)]
+pub struct FileSystem {
+    nodes: Vec<Node>,
+}
+
+impl Default for FileSystem {
+    fn default() -> FileSystem {
+        FileSystem::new()
+    }
+}
+
+impl FileSystem {
+    pub const ROOT: NodeId = 0;
+
+    pub fn new() -> FileSystem {
+        FileSystem {
+            nodes: vec![Node {
+                name: "/".to_owned(),
+                parent: None,
+                kind: NodeKind::Dir {
+                    children: Vec::new(),
+                },
+            }],
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    // The nodes directly in a directory, in the order they were added. Files
+    // have no children.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        match &self.nodes[id].kind {
+            NodeKind::Dir { children } => children,
+            NodeKind::File { .. } => &[],
+        }
+    }
+
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.children(dir)
+            .iter()
+            .copied()
+            .find(|&id| self.nodes[id].name == name)
+    }
+
+    // Adding a node that is already there returns it instead, so listing a
+    // directory twice changes nothing. A file listed again takes its new size.
+    pub fn add_dir(&mut self, parent: NodeId, name: &str) -> Result<NodeId, NameTaken> {
+        self.add(
+            parent,
+            name,
+            NodeKind::Dir {
+                children: Vec::new(),
+            },
+        )
+    }
+
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: u32) -> Result<NodeId, NameTaken> {
+        self.add(parent, name, NodeKind::File { size })
+    }
+
+    fn add(&mut self, parent: NodeId, name: &str, kind: NodeKind) -> Result<NodeId, NameTaken> {
+        if let Some(existing) = self.child(parent, name) {
+            return match (&mut self.nodes[existing].kind, kind) {
+                (NodeKind::Dir { .. }, NodeKind::Dir { .. }) => Ok(existing),
+                (NodeKind::File { size }, NodeKind::File { size: new_size }) => {
+                    *size = new_size;
+                    Ok(existing)
+                }
+                _ => Err(NameTaken {
+                    path: self.path(existing),
+                }),
+            };
+        }
+        let id = self.nodes.len();
+        match &mut self.nodes[parent].kind {
+            NodeKind::Dir { children } => children.push(id),
+            NodeKind::File { .. } => panic!("{} is not a directory", self.path(parent)),
+        }
+        self.nodes.push(Node {
+            name: name.to_owned(),
+            parent: Some(parent),
+            kind,
+        });
+        Ok(id)
+    }
+
+    // Follow `path` from the directory `from`. A path starting with `/` is
+    // followed from the root, and `..` goes up one level.
+    pub fn resolve(&self, from: NodeId, path: &str) -> Option<NodeId> {
+        let mut current = if path.starts_with('/') {
+            FileSystem::ROOT
+        } else {
+            from
+        };
+        for segment in path.split('/').filter(|s| !s.is_empty() && *s != ".") {
+            current = match segment {
+                ".." => self.nodes[current].parent?,
+                name => self.child(current, name)?,
+            };
+        }
+        Some(current)
+    }
+
+    // The absolute path of a node, with a trailing `/` for directories
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.nodes[current].parent {
+            names.push(self.nodes[current].name.as_str());
+            current = parent;
+        }
+        names.reverse();
+        let mut path = format!("/{}", names.join("/"));
+        if self.nodes[id].is_dir() && id != FileSystem::ROOT {
+            path.push('/');
+        }
+        path
+    }
+
+    // Every node below and including `from`, depth first
+    pub fn walk(&self, from: NodeId) -> Walk<'_> {
+        Walk {
+            file_system: self,
+            pending: vec![from],
+        }
+    }
+
+    // The size of every node, counting everything inside a directory. Children
+    // come after their parents, so one pass from the back adds up every
+    // directory.
+    pub fn total_sizes(&self) -> Vec<u32> {
+        let mut sizes = self
+            .nodes
+            .iter()
+            .map(|node| match node.kind {
+                NodeKind::File { size } => size,
+                NodeKind::Dir { .. } => 0,
+            })
+            .collect::<Vec<_>>();
+        for id in (1..self.nodes.len()).rev() {
+            let parent = self.nodes[id].parent.expect("Only the root has no parent");
+            sizes[parent] += sizes[id];
+        }
+        sizes
+    }
+}
+
+pub struct Walk<'a> {
+    file_system: &'a FileSystem,
+    pending: Vec<NodeId>,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.pending.pop()?;
+        self.pending
+            .extend(self.file_system.children(id).iter().rev());
+        Some(id)
+    }
+}
+
+// The tree in the layout the puzzle uses, one node per line
+impl fmt::Display for FileSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for id in self.walk(FileSystem::ROOT) {
+            let mut depth = 0;
+            let mut current = id;
+            while let Some(parent) = self.nodes[current].parent {
+                depth += 1;
+                current = parent;
+            }
+            let node = &self.nodes[id];
+            write!(f, "{:indent$}- {} ", "", node.name, indent = 2 * depth)?;
+            match node.kind {
+                NodeKind::Dir { .. } => writeln!(f, "(dir)")?,
+                NodeKind::File { size } => writeln!(f, "(file, size={size})")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod vfs_tests {
+    use super::*;
+
+    // /a/b.txt and /c/
+    fn small() -> FileSystem {
+        let mut file_system = FileSystem::new();
+        let a = file_system.add_dir(FileSystem::ROOT, "a").unwrap();
+        file_system.add_file(a, "b.txt", 10).unwrap();
+        file_system.add_dir(FileSystem::ROOT, "c").unwrap();
+        file_system
+    }
+
+    #[test]
+    fn paths_resolve_from_anywhere() {
+        let file_system = small();
+        let a = file_system.resolve(FileSystem::ROOT, "a").unwrap();
+        let c = file_system.resolve(a, "../c").unwrap();
+        assert_eq!(file_system.path(c), "/c/");
+        let b = file_system.resolve(c, "/a/b.txt").unwrap();
+        assert_eq!(file_system.path(b), "/a/b.txt");
+        assert_eq!(file_system.resolve(b, "/"), Some(FileSystem::ROOT));
+        assert_eq!(file_system.resolve(FileSystem::ROOT, ".."), None);
+        assert_eq!(file_system.resolve(FileSystem::ROOT, "a/x"), None);
+    }
+
+    #[test]
+    fn walk_is_depth_first_in_order_added() {
+        let file_system = small();
+        let paths = file_system
+            .walk(FileSystem::ROOT)
+            .map(|id| file_system.path(id))
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["/", "/a/", "/a/b.txt", "/c/"]);
+    }
+
+    #[test]
+    fn sizes_include_everything_below() {
+        let mut file_system = small();
+        let c = file_system.resolve(FileSystem::ROOT, "c").unwrap();
+        file_system.add_file(c, "d", 5).unwrap();
+        let sizes = file_system.total_sizes();
+        assert_eq!(sizes[FileSystem::ROOT], 15);
+        assert_eq!(sizes[c], 5);
+        assert_eq!(
+            file_system.node(c).kind,
+            NodeKind::Dir { children: vec![4] }
+        );
+    }
+
+    #[test]
+    fn adding_again_reuses_the_node() {
+        let mut file_system = small();
+        let a = file_system.add_dir(FileSystem::ROOT, "a").unwrap();
+        let b = file_system.add_file(a, "b.txt", 12).unwrap();
+        assert_eq!(file_system.resolve(FileSystem::ROOT, "a/b.txt"), Some(b));
+        assert_eq!(file_system.children(a), &[b]);
+        assert_eq!(file_system.total_sizes()[FileSystem::ROOT], 12);
+        assert_eq!(
+            file_system.add_file(FileSystem::ROOT, "c", 1),
+            Err(NameTaken {
+                path: "/c/".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn display_matches_the_puzzle() {
+        assert_eq!(
+            small().to_string(),
+            "- / (dir)\n  - a (dir)\n    - b.txt (file, size=10)\n  - c (dir)\n"
+        );
+    }
+}